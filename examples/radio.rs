@@ -0,0 +1,19 @@
+use gmusic::GoogleMusicApi;
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let client_id = std::env::var("CLIENT_ID").expect("missing client id");
+    let client_secret = std::env::var("CLIENT_SECRET").expect("missing client secret");
+
+    let api = GoogleMusicApi::new(client_id, client_secret, None).unwrap();
+    api.load_token().await.unwrap();
+
+    let tracks = api.get_lucky_tracks(25, &[]).await.unwrap();
+    println!("{:#?}", tracks);
+
+    if let Some(track) = tracks.first() {
+        let mix = api.get_instant_mix(track, 25).await.unwrap();
+        println!("{:#?}", mix);
+    }
+}
@@ -0,0 +1,21 @@
+use futures::TryStreamExt;
+use gmusic::GoogleMusicApi;
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let client_id = std::env::var("CLIENT_ID").expect("missing client id");
+    let client_secret = std::env::var("CLIENT_SECRET").expect("missing client secret");
+
+    let api = GoogleMusicApi::new(client_id, client_secret, None).unwrap();
+    api.load_token().await.unwrap();
+
+    let tracks = api.tracks_stream();
+    let handle = tokio::spawn(async move {
+        futures::pin_mut!(tracks);
+        while let Some(track) = tracks.try_next().await.unwrap() {
+            println!("{} - {}", track.artist, track.title);
+        }
+    });
+    handle.await.unwrap();
+}
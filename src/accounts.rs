@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::{GoogleMusicApi, TokenStore};
+
+/**
+ * Holds authenticated sessions of multiple accounts
+ *
+ * All sessions share the http connection pool and OAuth configuration of the api the manager was
+ * created with. Clones of the manager share the same accounts.
+ *
+ * # Example
+ * ```rust,no_run
+ * use gmusic::{AccountManager, GoogleMusicApi};
+ * use gmusic::token_store::FileTokenStore;
+ *
+ * #[tokio::main]
+ * async fn main() {
+ *   let api = GoogleMusicApi::new(String::new(), String::new(), None).unwrap();
+ *   let accounts = AccountManager::new(api);
+ *
+ *   for account_id in &["alice", "bob"] {
+ *     let store = FileTokenStore::new(format!("tokens/{}.json", account_id));
+ *     accounts.add_account(*account_id, store).load_token().await.unwrap();
+ *   }
+ *
+ *   let tracks = accounts.get("alice").unwrap().get_all_tracks().await.unwrap();
+ * }
+ * ```
+ */
+#[derive(Debug, Clone)]
+pub struct AccountManager {
+    api: GoogleMusicApi,
+    accounts: Arc<RwLock<HashMap<String, GoogleMusicApi>>>,
+}
+
+impl AccountManager {
+    pub fn new(api: GoogleMusicApi) -> AccountManager {
+        AccountManager {
+            api,
+            accounts: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /**
+     * Creates a new unauthenticated session for the given account, replacing an existing one
+     *
+     * The returned api still has to be logged in or load its token from the given store.
+     */
+    pub fn add_account<I, S>(&self, account_id: I, token_store: S) -> GoogleMusicApi
+    where
+        I: Into<String>,
+        S: TokenStore + 'static,
+    {
+        let session = self.api.new_session().with_token_store(token_store);
+        let mut accounts = self.accounts.write().unwrap_or_else(|err| err.into_inner());
+        accounts.insert(account_id.into(), session.clone());
+        session
+    }
+
+    /**
+     * Returns the session of the given account
+     */
+    pub fn get(&self, account_id: &str) -> Option<GoogleMusicApi> {
+        let accounts = self.accounts.read().unwrap_or_else(|err| err.into_inner());
+        accounts.get(account_id).cloned()
+    }
+
+    /**
+     * Removes the session of the given account without logging it out
+     */
+    pub fn remove(&self, account_id: &str) -> Option<GoogleMusicApi> {
+        let mut accounts = self.accounts.write().unwrap_or_else(|err| err.into_inner());
+        accounts.remove(account_id)
+    }
+
+    pub fn account_ids(&self) -> Vec<String> {
+        let accounts = self.accounts.read().unwrap_or_else(|err| err.into_inner());
+        accounts.keys().cloned().collect()
+    }
+}
@@ -1,47 +1,87 @@
-use tokio::fs::{read_to_string, write};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::error::Error as StdError;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use failure::{ensure, format_err, Error};
+use futures::future::{self, FutureExt};
+use futures::stream::{self, Stream, TryStreamExt};
 use hmac::{Hmac, Mac};
+use log::{debug, error};
 use oauth2::basic::BasicClient;
-use oauth2::{AuthUrl, ClientId, ClientSecret, PkceCodeVerifier, RedirectUrl, TokenUrl};
+use oauth2::{PkceCodeVerifier, TokenResponse};
 use reqwest::header::HeaderMap;
-use reqwest::{header::AUTHORIZATION, Method, RequestBuilder, Response, StatusCode};
+use reqwest::{header::AUTHORIZATION, Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
 use sha1::Sha1;
+use tokio::time::delay_for;
 use url::Url;
+use uuid::Uuid;
 
-use crate::auth::{get_oauth_url, perform_oauth, request_token};
+use crate::auth::{
+    complete_login, get_oauth_url, perform_device_oauth, perform_loopback_oauth, perform_oauth,
+    request_token, revoke_token, DeviceAuthorization, PendingLogin,
+};
+use crate::builder::GoogleMusicApiBuilder;
+use crate::error::GmusicError;
+use crate::http_client::HttpClientConfig;
 use crate::models::album::Album;
-use crate::models::all_playlists::Playlist;
-use crate::models::all_playlists::{GetAllPlaylistsRequest, GetAllPlaylistsResponse};
-use crate::models::all_tracks::Track;
-use crate::models::all_tracks::{GetAllTracksRequest, GetAllTracksResponse};
+use crate::models::all_playlists::{Playlist, PlaylistShareState};
+use crate::models::all_tracks::{Track, TrackRating};
 use crate::models::artist::Artist;
 use crate::models::device_management_info::{
     DeviceManagementInfo, GetDeviceManagementInfoResponse,
 };
+use crate::models::edit_station::{StationCreate, StationMutation};
+use crate::models::playlist_batch::{PlaylistCreate, PlaylistMutation, PlaylistUpdate};
+use crate::models::playlist_entries::GetPlaylistEntriesRequest;
 use crate::models::playlist_entries::PlaylistEntry;
-use crate::models::playlist_entries::{GetPlaylistEntriesRequest, GetPlaylistEntriesResponse};
+use crate::models::playlist_entries_batch::{
+    PlaylistEntryCreate, PlaylistEntryMutation, PlaylistEntryUpdate,
+};
 use crate::models::search_results::{SearchResultCluster, SearchResultResponse};
-use crate::token::AuthToken;
-use crate::models::shared_playlist_entries::{SharedPlaylistEntry, SharedPlaylistEntriesResponse, SharedPlaylistContentsRequest, SharedPlaylistContentsFilter};
+use crate::models::shared_playlist_entries::{
+    SharedPlaylistContentsFilter, SharedPlaylistContentsRequest, SharedPlaylistEntriesResponse,
+    SharedPlaylistEntry,
+};
+use crate::models::station::{Station, StationSeed};
+use crate::models::station_feed::{
+    RecentlyPlayedTrack, StationFeedFilter, StationFeedRequest, StationFeedResponse,
+};
+use crate::models::track_batch::{TrackCreate, TrackMutation};
+use crate::models::track_stats::{TrackEvent, TrackStat, TrackStatsRequest, TrackStatsResponse};
+use crate::models::{
+    GMusicListResponse, MutateRequest, MutateResponse, MutateResult, Page, PageRequest,
+};
+use crate::play_events::PlayEvent;
+use crate::rate_limit::{EndpointCategory, RateLimiter};
+use crate::retry::{parse_retry_after, RetryPolicy};
+use crate::token::{AuthToken, BackgroundRefresh};
+use crate::token_store::{MemoryTokenStore, StoredToken, TokenStore};
 
-static BASE_URL: &str = "https://mclients.googleapis.com/sj/v2.5/";
-static STREAM_URL: &str = "https://mclients.googleapis.com/music/mplay";
+static LUCKY_STATION_ID: &str = "IFL";
 pub static CODE_REDIRECT_URI: &str = "urn:ietf:wg:oauth:2.0:oob";
 
 #[derive(Debug, Clone)]
 pub struct GoogleMusicApi {
-    auth_token: AuthToken,
-    device_id: Option<String>,
-    client: GoogleMusicApiClient,
+    pub(crate) auth_token: AuthToken,
+    pub(crate) device_id: Option<String>,
+    pub(crate) client: GoogleMusicApiClient,
+    pub(crate) token_store: Arc<dyn TokenStore>,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct GoogleMusicApiClient {
     pub id: String,
     pub secret: String,
-    oauth_client: BasicClient,
+    pub oauth_client: BasicClient,
+    pub token_url: String,
+    pub http_client: reqwest::Client,
+    pub base_url: String,
+    pub stream_url: String,
+    pub locale: String,
+    pub tier: String,
+    pub retry_policy: RetryPolicy,
+    pub rate_limiter: RateLimiter,
 }
 
 struct Headers(Vec<(&'static str, String)>);
@@ -66,33 +106,122 @@ impl GoogleMusicApi {
         client_id: String,
         client_secret: String,
         redirect_uri: Option<&str>,
-    ) -> Result<GoogleMusicApi, Error> {
-        let oauth_client = BasicClient::new(
-            ClientId::new(client_id.clone()),
-            Some(ClientSecret::new(client_secret.clone())),
-            AuthUrl::new("https://accounts.google.com/o/oauth2/v2/auth".to_string())?,
-            Some(TokenUrl::new(
-                "https://www.googleapis.com/oauth2/v3/token".to_string(),
-            )?),
+    ) -> Result<GoogleMusicApi, GmusicError> {
+        let mut builder = GoogleMusicApiBuilder::new(client_id, client_secret);
+        if let Some(redirect_uri) = redirect_uri {
+            builder = builder.with_redirect_uri(redirect_uri);
+        }
+        builder.build()
+    }
+
+    pub fn builder(client_id: String, client_secret: String) -> GoogleMusicApiBuilder {
+        GoogleMusicApiBuilder::new(client_id, client_secret)
+    }
+
+    /**
+     * Configures where `store_token` and `load_token` persist the token
+     *
+     * Defaults to a `.google-auth.json` file in the current working directory.
+     *
+     * # Example
+     * ```rust,no_run
+     * use gmusic::GoogleMusicApi;
+     * use gmusic::token_store::FileTokenStore;
+     *
+     * let api = GoogleMusicApi::new(String::new(), String::new(), None)
+     *   .unwrap()
+     *   .with_token_store(FileTokenStore::xdg("my-player").unwrap());
+     * ```
+     */
+    pub fn with_token_store<S: TokenStore + 'static>(mut self, store: S) -> Self {
+        self.token_store = Arc::new(store);
+        self
+    }
+
+    /**
+     * Replaces the http client used for all api requests
+     *
+     * The client is shared with all clones and sessions created afterwards.
+     */
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client.http_client = client;
+        self
+    }
+
+    /**
+     * Configures how failed requests are retried, defaults to [RetryPolicy::default](struct.RetryPolicy.html#impl-Default)
+     */
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.client.retry_policy = policy;
+        self
+    }
+
+    /**
+     * Limits how many requests are sent, shared with all clones and sessions created afterwards
+     */
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.client.rate_limiter = limiter;
+        self
+    }
+
+    /**
+     * Replaces the http client used for all api requests with one built from the given configuration
+     */
+    pub fn with_http_config(self, config: HttpClientConfig) -> Result<Self, GmusicError> {
+        let client = config.build()?;
+        Ok(self.with_http_client(client))
+    }
+
+    /**
+     * Refreshes the access token in the background the given margin before it expires
+     *
+     * Requests then don't have to wait for the refresh. Requires a running tokio runtime,
+     * the refresh stops when the returned handle is dropped.
+     *
+     * # Example
+     * ```rust,no_run
+     * use std::time::Duration;
+     * use gmusic::GoogleMusicApi;
+     *
+     * #[tokio::main]
+     * async fn main() {
+     *   let api = GoogleMusicApi::new(String::new(), String::new(), None).unwrap();
+     *   api.load_token().await.unwrap();
+     *   let _refresh = api.refresh_in_background(Duration::from_secs(60));
+     *
+     *   let tracks = api.get_all_tracks().await.unwrap();
+     * }
+     * ```
+     */
+    pub fn refresh_in_background(&self, margin: Duration) -> BackgroundRefresh {
+        self.auth_token.spawn_background_refresh(
+            self.client.oauth_client.clone(),
+            self.client.http_client.clone(),
+            margin,
         )
-        .set_redirect_url(RedirectUrl::new(
-            redirect_uri.unwrap_or(CODE_REDIRECT_URI).to_string(),
-        )?);
-
-        Ok(GoogleMusicApi {
-            client: GoogleMusicApiClient {
-                id: client_id,
-                secret: client_secret,
-                oauth_client,
-            },
+    }
+
+    /**
+     * Creates a new unauthenticated session sharing the http connection pool and OAuth configuration
+     *
+     * Clones of an api share their token, a new session is required to login another account.
+     * The session keeps its token in memory until another token store is configured.
+     */
+    pub fn new_session(&self) -> GoogleMusicApi {
+        GoogleMusicApi {
             auth_token: AuthToken::new(),
-            device_id: None,
-        })
+            device_id: self.device_id.clone(),
+            client: self.client.clone(),
+            token_store: Arc::new(MemoryTokenStore::new()),
+        }
     }
 
     /**
      * Perform an OAuth Login
      *
+     * The handler receives the authorize url and resolves to the authorization code entered by the user.
+     * Errors returned by the handler abort the login with [GmusicError::LoginHandler](enum.GmusicError.html#variant.LoginHandler).
+     *
      * Available handlers:
      * * [auth::stdio_login](auth/fn.stdio_login.html)
      *
@@ -108,25 +237,136 @@ impl GoogleMusicApi {
      * }
      * ```
      */
-    pub async fn login<H>(&self, handler: H) -> Result<(), Error>
+    pub async fn login<H, F, E>(&self, handler: H) -> Result<(), GmusicError>
+    where
+        H: FnOnce(String) -> F,
+        F: Future<Output = Result<String, E>>,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let token =
+            perform_oauth(&self.client.oauth_client, &self.client.http_client, handler).await?;
+        self.auth_token.set_token(token).await;
+        Ok(())
+    }
+
+    /**
+     * Perform an OAuth Login by redirecting the browser to a local http listener
+     *
+     * The handler receives the authorize url and should open it in the browser of the user.
+     * The authorization code and state are captured from the redirect to `http://127.0.0.1:<port>`,
+     * which has to be allowed for the configured client id.
+     *
+     * Available handlers:
+     * * [auth::print_login_url](auth/fn.print_login_url.html)
+     *
+     * # Example
+     * ```rust,no_run
+     * use gmusic::{GoogleMusicApi, auth::print_login_url};
+     *
+     * #[tokio::main]
+     * async fn main() {
+     *   let api = GoogleMusicApi::new(String::new(), String::new(), None).unwrap();
+     *
+     *   api.login_loopback(print_login_url).await.unwrap();
+     * }
+     * ```
+     */
+    pub async fn login_loopback<H>(&self, handler: H) -> Result<(), GmusicError>
+    where
+        H: Fn(&str),
+    {
+        let token =
+            perform_loopback_oauth(&self.client.oauth_client, &self.client.http_client, handler)
+                .await?;
+        self.auth_token.set_token(token).await;
+        Ok(())
+    }
+
+    /**
+     * Perform an OAuth Login on a device without browser or keyboard
+     *
+     * The handler receives a user code which has to be entered at the verification url on
+     * another device. The token endpoint is polled until the user authorized the login.
+     *
+     * Available handlers:
+     * * [auth::print_device_code](auth/fn.print_device_code.html)
+     *
+     * # Example
+     * ```rust,no_run
+     * use gmusic::{GoogleMusicApi, auth::print_device_code};
+     *
+     * #[tokio::main]
+     * async fn main() {
+     *   let api = GoogleMusicApi::new(String::new(), String::new(), None).unwrap();
+     *
+     *   api.login_device(print_device_code).await.unwrap();
+     * }
+     * ```
+     */
+    pub async fn login_device<H>(&self, handler: H) -> Result<(), GmusicError>
     where
-        H: Fn(String) -> String,
+        H: Fn(&DeviceAuthorization),
     {
-        let token = perform_oauth(&self.client.oauth_client, handler).await?;
+        let token = perform_device_oauth(&self.client, handler).await?;
         self.auth_token.set_token(token).await;
         Ok(())
     }
 
     pub fn get_oauth_url(&self) -> (String, String) {
-        let (url, verifier) = get_oauth_url(&self.client.oauth_client);
+        let pending = get_oauth_url(&self.client.oauth_client);
+
+        (pending.url, pending.verifier)
+    }
 
-        (url, verifier.secret().clone())
+    /**
+     * Starts an OAuth login driven by a redirect to the configured redirect uri
+     *
+     * Redirect the user to `url` of the returned login and pass it to
+     * [complete_login](#method.complete_login) together with the `code` and `state`
+     * query parameters of the redirect.
+     */
+    pub fn begin_login(&self) -> PendingLogin {
+        get_oauth_url(&self.client.oauth_client)
     }
 
-    pub async fn request_token(&mut self, code: String, verifier: String) -> Result<(), Error> {
+    /**
+     * Exchanges the authorization code of a redirect for a token
+     *
+     * Fails without requesting a token when `state` does not match the state of the pending login.
+     */
+    pub async fn complete_login(
+        &self,
+        pending: PendingLogin,
+        code: String,
+        state: &str,
+    ) -> Result<(), GmusicError> {
+        let token = complete_login(
+            &self.client.oauth_client,
+            &self.client.http_client,
+            pending,
+            code,
+            state,
+        )
+        .await?;
+        self.auth_token.set_token(token).await;
+
+        Ok(())
+    }
+
+    pub async fn request_token(
+        &mut self,
+        code: String,
+        verifier: String,
+    ) -> Result<(), GmusicError> {
         let verifier = PkceCodeVerifier::new(verifier);
 
-        let token = request_token(&self.client.oauth_client, code, verifier).await?;
+        let token = request_token(
+            &self.client.oauth_client,
+            &self.client.http_client,
+            code,
+            verifier,
+        )
+        .await?;
         self.auth_token.set_token(token).await;
 
         Ok(())
@@ -137,63 +377,248 @@ impl GoogleMusicApi {
     }
 
     /**
-     * Stores the auth and refresh token in a `.google-auth.json` file for login without user input.
+     * Revokes the token at Google, forgets it and removes it from the configured token store
+     *
+     * The token is removed locally even when revoking it fails.
      */
-    pub async fn store_token(&self) -> Result<(), Error> {
-        ensure!(self.auth_token.has_token(), "No token available to persist");
-        let token = serde_json::to_string(&self.auth_token.get_token().await?)?;
-        write(".google-auth.json", token).await?; // TODO: configure file path
-        Ok(())
+    pub async fn logout(&self) -> Result<(), GmusicError> {
+        let revoked = match self.auth_token.get_token().await {
+            Ok(token) => {
+                let secret = match token.refresh_token() {
+                    Some(refresh_token) => refresh_token.secret(),
+                    None => token.access_token().secret(),
+                };
+                revoke_token(&self.client.http_client, secret).await
+            }
+            Err(_) => Ok(()),
+        };
+        self.auth_token.clear().await;
+        self.token_store.clear().await?;
+
+        revoked
     }
 
     /**
-     * Stores the auth and refresh token from a `.google-auth.json` file for login without user input.
+     * Stores the auth and refresh token in the configured token store for login without user input.
      */
-    pub async fn load_token(&self) -> Result<(), Error> {
-        let token = read_to_string(".google-auth.json").await?;
-        let token = serde_json::from_str(&token)?;
-        self.auth_token.set_token(token).await;
+    pub async fn store_token(&self) -> Result<(), GmusicError> {
+        if !self.auth_token.has_token() {
+            return Err(GmusicError::NotLoggedIn);
+        }
+        let token = self.auth_token.get_stored_token().await?;
+        self.token_store.store(&token).await
+    }
+
+    /**
+     * Loads the auth and refresh token from the configured token store for login without user input.
+     */
+    pub async fn load_token(&self) -> Result<(), GmusicError> {
+        let token = self
+            .token_store
+            .load()
+            .await?
+            .ok_or(GmusicError::NotLoggedIn)?;
+        self.auth_token.restore_token(token).await;
         Ok(())
     }
 
+    /**
+     * Registers a callback which is awaited whenever the token changes after a login or refresh
+     *
+     * Replaces a previously registered callback or [persist_token_changes](#method.persist_token_changes).
+     *
+     * # Example
+     * ```rust,no_run
+     * use gmusic::GoogleMusicApi;
+     *
+     * let api = GoogleMusicApi::new(String::new(), String::new(), None).unwrap();
+     * api.on_token_change(|token| async move {
+     *   println!("token expires at {:?}", token.expires_at());
+     * });
+     * ```
+     */
+    pub fn on_token_change<F, R>(&self, callback: F)
+    where
+        F: Fn(StoredToken) -> R + Send + Sync + 'static,
+        R: Future<Output = ()> + Send + 'static,
+    {
+        self.auth_token
+            .set_listener(Arc::new(move |token| callback(token).boxed()));
+    }
+
+    /**
+     * Stores the token in the configured token store whenever it changes after a login or refresh
+     *
+     * Failures to store the token are logged and don't affect the refresh.
+     */
+    pub fn persist_token_changes(&self) {
+        let token_store = self.token_store.clone();
+        self.on_token_change(move |token| {
+            let token_store = token_store.clone();
+            async move {
+                if let Err(err) = token_store.store(&token).await {
+                    error!("unable to persist token: {}", err);
+                }
+            }
+        });
+    }
+
     /**
      * Returns a list of all user tracks
      */
-    // TODO: paging
-    pub async fn get_all_tracks(&self) -> Result<Vec<Track>, Error> {
-        let body = GetAllTracksRequest::new();
-        let url = format!("{}trackfeed", BASE_URL);
-        let res: GetAllTracksResponse = self
-            .api_post(url, &body, Headers::new(), Headers::new())
-            .await?
-            .json()
-            .await?;
+    pub async fn get_all_tracks(&self) -> Result<Vec<Track>, GmusicError> {
+        GoogleMusicApi::collect_pages(|page| self.get_tracks_page(page, None)).await
+    }
 
-        Ok(res.data.items)
+    /**
+     * Returns a single page of user tracks
+     *
+     * Pass the `next_page_token` of the previous page as `start_token` to continue.
+     */
+    pub async fn get_tracks_page(
+        &self,
+        start_token: Option<String>,
+        max_results: Option<u32>,
+    ) -> Result<Page<Track>, GmusicError> {
+        self.get_feed_page("trackfeed", start_token, max_results, None)
+            .await
+    }
+
+    /**
+     * Returns a list of all user tracks which have been added, modified or deleted since the given time
+     */
+    pub async fn get_all_tracks_since(&self, since: SystemTime) -> Result<Vec<Track>, GmusicError> {
+        GoogleMusicApi::collect_pages(|page| {
+            self.get_feed_page("trackfeed", page, None, Some(since))
+        })
+        .await
     }
 
     /**
      * Returns a list of all playlists a user has created or subscribed to
      */
-    // TODO: paging
-    pub async fn get_all_playlists(&self) -> Result<Vec<Playlist>, Error> {
-        let body = GetAllPlaylistsRequest::new();
-        let url = format!("{}playlistfeed", BASE_URL);
-        let res: GetAllPlaylistsResponse = self
-            .api_post(url, &body, Headers::new(), Headers::new())
-            .await?
-            .json()
+    pub async fn get_all_playlists(&self) -> Result<Vec<Playlist>, GmusicError> {
+        GoogleMusicApi::collect_pages(|page| self.get_playlists_page(page, None)).await
+    }
+
+    /**
+     * Returns a single page of playlists a user has created or subscribed to
+     *
+     * Pass the `next_page_token` of the previous page as `start_token` to continue.
+     */
+    pub async fn get_playlists_page(
+        &self,
+        start_token: Option<String>,
+        max_results: Option<u32>,
+    ) -> Result<Page<Playlist>, GmusicError> {
+        self.get_feed_page("playlistfeed", start_token, max_results, None)
+            .await
+    }
+
+    /**
+     * Returns a list of all playlists which have been created, modified or deleted since the given time
+     */
+    pub async fn get_all_playlists_since(
+        &self,
+        since: SystemTime,
+    ) -> Result<Vec<Playlist>, GmusicError> {
+        GoogleMusicApi::collect_pages(|page| {
+            self.get_feed_page("playlistfeed", page, None, Some(since))
+        })
+        .await
+    }
+
+    /**
+     * Returns the playlist with the given id
+     */
+    pub async fn get_playlist(&self, playlist_id: &str) -> Result<Playlist, GmusicError> {
+        let playlists = self
+            .playlists_stream()
+            .try_filter(|playlist| future::ready(playlist.id == playlist_id));
+        futures::pin_mut!(playlists);
+        let playlist = playlists.try_next().await?;
+
+        playlist.ok_or(GmusicError::NotFound)
+    }
+
+    /**
+     * Creates a new user generated playlist
+     *
+     * The returned playlist is built from the given fields, the share token is not known yet.
+     */
+    pub async fn create_playlist(
+        &self,
+        name: &str,
+        description: Option<&str>,
+        share_state: PlaylistShareState,
+    ) -> Result<Playlist, GmusicError> {
+        let create =
+            PlaylistCreate::new(name.to_string(), description.map(String::from), share_state);
+        let id = self
+            .mutate_playlist(PlaylistMutation::Create(create.clone()))
             .await?;
 
-        Ok(res.data.items)
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros();
+        Ok(create.into_playlist(id, timestamp.to_string()))
+    }
+
+    /**
+     * Updates the given fields of a playlist, fields which are `None` are left untouched
+     *
+     * Returns the playlist with the changes applied.
+     */
+    pub async fn update_playlist(
+        &self,
+        playlist: &Playlist,
+        name: Option<&str>,
+        description: Option<&str>,
+        share_state: Option<PlaylistShareState>,
+    ) -> Result<Playlist, GmusicError> {
+        let update = PlaylistUpdate {
+            id: playlist.id.clone(),
+            name: name.map(String::from),
+            description: description.map(String::from),
+            share_state,
+        };
+        self.mutate_playlist(PlaylistMutation::Update(update.clone()))
+            .await?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros();
+        let mut playlist = playlist.clone();
+        update.apply(&mut playlist, timestamp.to_string());
+        Ok(playlist)
+    }
+
+    pub async fn rename_playlist(
+        &self,
+        playlist: &Playlist,
+        name: &str,
+    ) -> Result<Playlist, GmusicError> {
+        self.update_playlist(playlist, Some(name), None, None).await
+    }
+
+    pub async fn delete_playlist(&self, playlist_id: &str) -> Result<(), GmusicError> {
+        self.mutate_playlist(PlaylistMutation::Delete(playlist_id.to_string()))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn mutate_playlist(&self, mutation: PlaylistMutation) -> Result<String, GmusicError> {
+        let mut ids = self
+            .mutate_ids("playlistbatch", vec![mutation], "playlist")
+            .await?;
+        Ok(ids.remove(0))
     }
 
     /**
      * Returns a list of the devices the user has used Google Play Music on
      */
     // TODO: paging
-    pub async fn get_device_management_info(&self) -> Result<Vec<DeviceManagementInfo>, Error> {
-        let url = format!("{}devicemanagementinfo", BASE_URL);
+    pub async fn get_device_management_info(
+        &self,
+    ) -> Result<Vec<DeviceManagementInfo>, GmusicError> {
+        let url = format!("{}devicemanagementinfo", self.client.base_url);
         let res: GetDeviceManagementInfoResponse = self
             .api_get(url, Headers::new(), Headers::new())
             .await?
@@ -206,55 +631,329 @@ impl GoogleMusicApi {
     /**
      * Returns the tracks used in all user created playlists
      */
-    pub async fn get_playlist_entries(&self) -> Result<Vec<PlaylistEntry>, Error> {
-        let mut items = Vec::new();
+    pub async fn get_playlist_entries(&self) -> Result<Vec<PlaylistEntry>, GmusicError> {
+        GoogleMusicApi::collect_pages(|page| self.get_playlist_entries_page(page, Some(20000)))
+            .await
+    }
+
+    /**
+     * Returns a single page of the tracks used in all user created playlists
+     *
+     * Pass the `next_page_token` of the previous page as `start_token` to continue.
+     */
+    pub async fn get_playlist_entries_page(
+        &self,
+        start_token: Option<String>,
+        max_results: Option<u32>,
+    ) -> Result<Page<PlaylistEntry>, GmusicError> {
+        self.get_playlist_entries_feed_page(start_token, max_results, None)
+            .await
+    }
+
+    /**
+     * Returns the playlist entries which have been added, modified or deleted since the given time
+     */
+    pub async fn get_playlist_entries_since(
+        &self,
+        since: SystemTime,
+    ) -> Result<Vec<PlaylistEntry>, GmusicError> {
+        GoogleMusicApi::collect_pages(|page| {
+            self.get_playlist_entries_feed_page(page, Some(20000), Some(since))
+        })
+        .await
+    }
 
-        let mut res = self.get_playlist_entries_page(None).await?;
-        items.append(&mut res.data.items);
+    async fn get_playlist_entries_feed_page(
+        &self,
+        start_token: Option<String>,
+        max_results: Option<u32>,
+        updated_min: Option<SystemTime>,
+    ) -> Result<Page<PlaylistEntry>, GmusicError> {
+        let mut page: Page<PlaylistEntry> = self
+            .get_feed_page("plentryfeed", start_token, max_results, updated_min)
+            .await?;
 
-        let mut next_page_token = res.next_page_token;
-        while next_page_token.is_some() {
-            let mut res = self.get_playlist_entries_page(next_page_token).await?;
-            items.append(&mut res.data.items);
-            next_page_token = res.next_page_token;
+        for entry in &mut page.items {
+            if let Some(track) = entry.track.as_mut() {
+                track.id = entry.track_id.clone()
+            }
         }
 
-        Ok(items)
+        Ok(page)
     }
 
-    async fn get_playlist_entries_page(
+    async fn get_feed_page<T>(
         &self,
-        page: Option<String>,
-    ) -> Result<GetPlaylistEntriesResponse, Error> {
-        let url = format!("{}plentryfeed", BASE_URL);
-        let request = GetPlaylistEntriesRequest {
-            max_results: Some(String::from("20000")),
-            start_token: page,
-        };
-        let mut res: GetPlaylistEntriesResponse = self
-            .api_post(url, &request, Headers::new(), Headers::new())
+        feed: &str,
+        start_token: Option<String>,
+        max_results: Option<u32>,
+        updated_min: Option<SystemTime>,
+    ) -> Result<Page<T>, GmusicError>
+    where
+        T: DeserializeOwned,
+    {
+        let url = format!("{}{}", self.client.base_url, feed);
+        let body = PageRequest::new(start_token, max_results);
+        let mut params = Headers::new();
+        if let Some(updated_min) = updated_min {
+            let micros = updated_min.duration_since(UNIX_EPOCH)?.as_micros();
+            params = params.append("updated-min", micros.to_string());
+        }
+        let res: GMusicListResponse<T> = self
+            .api_post(url, &body, Headers::new(), params)
             .await?
             .json()
             .await?;
 
-        for entry in &mut res.data.items {
-            if let Some(mut track) = entry.track.as_mut() {
-                track.id = entry.track_id.clone()
+        Ok(res.into())
+    }
+
+    /**
+     * Returns a stream of all user tracks, fetching pages as the stream is polled
+     */
+    pub fn tracks_stream(&self) -> impl Stream<Item = Result<Track, GmusicError>> {
+        GoogleMusicApi::flatten_pages(self.tracks_page_stream(None))
+    }
+
+    /**
+     * Returns a stream of pages of user tracks starting at the given page token
+     *
+     * The `next_page_token` of the last received page can be used to resume the stream later on.
+     */
+    pub fn tracks_page_stream(
+        &self,
+        start_token: Option<String>,
+    ) -> impl Stream<Item = Result<Page<Track>, GmusicError>> {
+        let api = self.clone();
+        GoogleMusicApi::page_stream(start_token, move |page| {
+            let api = api.clone();
+            async move { api.get_tracks_page(page, None).await }
+        })
+    }
+
+    /**
+     * Returns a stream of all playlists, fetching pages as the stream is polled
+     */
+    pub fn playlists_stream(&self) -> impl Stream<Item = Result<Playlist, GmusicError>> {
+        GoogleMusicApi::flatten_pages(self.playlists_page_stream(None))
+    }
+
+    /**
+     * Returns a stream of pages of playlists starting at the given page token
+     *
+     * The `next_page_token` of the last received page can be used to resume the stream later on.
+     */
+    pub fn playlists_page_stream(
+        &self,
+        start_token: Option<String>,
+    ) -> impl Stream<Item = Result<Page<Playlist>, GmusicError>> {
+        let api = self.clone();
+        GoogleMusicApi::page_stream(start_token, move |page| {
+            let api = api.clone();
+            async move { api.get_playlists_page(page, None).await }
+        })
+    }
+
+    /**
+     * Returns a stream of all playlist entries, fetching pages as the stream is polled
+     */
+    pub fn playlist_entries_stream(
+        &self,
+    ) -> impl Stream<Item = Result<PlaylistEntry, GmusicError>> {
+        GoogleMusicApi::flatten_pages(self.playlist_entries_page_stream(None))
+    }
+
+    /**
+     * Returns a stream of pages of playlist entries starting at the given page token
+     *
+     * The `next_page_token` of the last received page can be used to resume the stream later on.
+     */
+    pub fn playlist_entries_page_stream(
+        &self,
+        start_token: Option<String>,
+    ) -> impl Stream<Item = Result<Page<PlaylistEntry>, GmusicError>> {
+        let api = self.clone();
+        GoogleMusicApi::page_stream(start_token, move |page| {
+            let api = api.clone();
+            async move { api.get_playlist_entries_page(page, Some(20000)).await }
+        })
+    }
+
+    async fn collect_pages<T, F, R>(get_page: F) -> Result<Vec<T>, GmusicError>
+    where
+        F: FnMut(Option<String>) -> R,
+        R: Future<Output = Result<Page<T>, GmusicError>>,
+    {
+        GoogleMusicApi::page_stream(None, get_page)
+            .map_ok(|page| page.items)
+            .try_concat()
+            .await
+    }
+
+    fn page_stream<T, F, R>(
+        start_token: Option<String>,
+        mut get_page: F,
+    ) -> impl Stream<Item = Result<Page<T>, GmusicError>>
+    where
+        F: FnMut(Option<String>) -> R,
+        R: Future<Output = Result<Page<T>, GmusicError>>,
+    {
+        // the outer option marks whether there is another page to fetch
+        stream::try_unfold(Some(start_token), move |page_token| {
+            let page = page_token.map(&mut get_page);
+            async move {
+                if let Some(page) = page {
+                    let page = page.await?;
+                    let next_page_token = page.next_page_token.clone().map(Some);
+                    Ok(Some((page, next_page_token)))
+                } else {
+                    Ok(None)
+                }
             }
-        }
+        })
+    }
+
+    fn flatten_pages<T>(
+        pages: impl Stream<Item = Result<Page<T>, GmusicError>>,
+    ) -> impl Stream<Item = Result<T, GmusicError>> {
+        pages
+            .map_ok(|page| stream::iter(page.items.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /**
+     * Appends the given library or store tracks to the end of a playlist
+     *
+     * Returns the ids of the created playlist entries.
+     */
+    pub async fn add_songs_to_playlist(
+        &self,
+        playlist_id: &str,
+        track_ids: &[&str],
+    ) -> Result<Vec<String>, GmusicError> {
+        let client_ids: Vec<String> = track_ids
+            .iter()
+            .map(|_| Uuid::new_v4().to_string())
+            .collect();
+        // new entries are chained by their client ids to keep them in order
+        let mutations = track_ids
+            .iter()
+            .enumerate()
+            .map(|(i, track_id)| {
+                let mut create = PlaylistEntryCreate::new(
+                    client_ids[i].clone(),
+                    playlist_id.to_string(),
+                    track_id.to_string(),
+                );
+                if i > 0 {
+                    create.preceding_entry_id = Some(client_ids[i - 1].clone());
+                }
+                create.following_entry_id = client_ids.get(i + 1).cloned();
+                PlaylistEntryMutation::Create(create)
+            })
+            .collect();
+
+        self.mutate_playlist_entries(mutations).await
+    }
+
+    /**
+     * Removes the playlist entries with the given ids
+     *
+     * Returns the ids of the removed playlist entries.
+     */
+    pub async fn remove_entries_from_playlist(
+        &self,
+        entry_ids: &[&str],
+    ) -> Result<Vec<String>, GmusicError> {
+        let mutations = entry_ids
+            .iter()
+            .map(|id| PlaylistEntryMutation::Delete(id.to_string()))
+            .collect();
+
+        self.mutate_playlist_entries(mutations).await
+    }
+
+    /**
+     * Moves a playlist entry between the two given entries
+     *
+     * `None` for `preceding` moves the entry to the start, `None` for `following` moves it to the end.
+     * Use [move_playlist_entry_after](#method.move_playlist_entry_after) to only specify the target position.
+     */
+    pub async fn reorder_playlist_entry(
+        &self,
+        entry: &PlaylistEntry,
+        preceding: Option<&PlaylistEntry>,
+        following: Option<&PlaylistEntry>,
+    ) -> Result<(), GmusicError> {
+        let update = PlaylistEntryUpdate::reorder(
+            entry,
+            preceding.map(|entry| entry.client_id.clone()),
+            following.map(|entry| entry.client_id.clone()),
+        );
+        self.mutate_playlist_entries(vec![PlaylistEntryMutation::Update(update)])
+            .await?;
+
+        Ok(())
+    }
+
+    /**
+     * Moves a playlist entry directly behind `after` or to the start when `after` is `None`
+     *
+     * Fetches the current playlist entries to determine the surrounding entries.
+     */
+    pub async fn move_playlist_entry_after(
+        &self,
+        entry: &PlaylistEntry,
+        after: Option<&PlaylistEntry>,
+    ) -> Result<(), GmusicError> {
+        let mut entries: Vec<PlaylistEntry> = self
+            .get_playlist_entries()
+            .await?
+            .into_iter()
+            .filter(|e| e.playlist_id == entry.playlist_id && !e.deleted && e.id != entry.id)
+            .collect();
+        entries.sort_by(|a, b| {
+            (a.absolute_position.len(), &a.absolute_position)
+                .cmp(&(b.absolute_position.len(), &b.absolute_position))
+        });
+
+        let following_index = match after {
+            Some(after) => {
+                let index = entries
+                    .iter()
+                    .position(|e| e.id == after.id)
+                    .ok_or_else(|| {
+                        GmusicError::InvalidArgument(format!(
+                            "Entry {} is not part of playlist {}",
+                            after.id, entry.playlist_id
+                        ))
+                    })?;
+                index + 1
+            }
+            None => 0,
+        };
+        let following = entries.get(following_index);
 
-        Ok(res)
+        self.reorder_playlist_entry(entry, after, following).await
     }
 
-    pub async fn get_shared_playlist_contents(&self, share_token: &str) -> Result<Vec<SharedPlaylistEntry>, Error> {
-        let url = format!("{}plentries/shared", BASE_URL);
+    async fn mutate_playlist_entries(
+        &self,
+        mutations: Vec<PlaylistEntryMutation>,
+    ) -> Result<Vec<String>, GmusicError> {
+        self.mutate_ids("plentriesbatch", mutations, "playlist entry")
+            .await
+    }
+
+    pub async fn get_shared_playlist_contents(
+        &self,
+        share_token: &str,
+    ) -> Result<Vec<SharedPlaylistEntry>, GmusicError> {
+        let url = format!("{}plentries/shared", self.client.base_url);
         let request = SharedPlaylistContentsRequest {
             entries: vec![SharedPlaylistContentsFilter {
                 share_token: share_token.into(),
-                pages: GetPlaylistEntriesRequest {
-                    max_results: Some(String::from("200")),
-                    start_token: None
-                }
+                pages: GetPlaylistEntriesRequest::new(None, Some(200)),
             }],
         };
         let mut res: SharedPlaylistEntriesResponse = self
@@ -268,10 +967,12 @@ impl GoogleMusicApi {
         Ok(entry.playlist_entry)
     }
 
-    pub async fn get_store_track(&self, track_id: &str) -> Result<Track, Error> {
-        ensure!(track_id.starts_with("T"), "track_id is not a store id");
+    pub async fn get_store_track(&self, track_id: &str) -> Result<Track, GmusicError> {
+        if !track_id.starts_with("T") {
+            return Err(GmusicError::InvalidId(track_id.to_string()));
+        }
         let params = Headers::new().append("alt", "json").append("nid", track_id);
-        let url = format!("{}fetchtrack", BASE_URL);
+        let url = format!("{}fetchtrack", self.client.base_url);
         let track: Track = self
             .api_get(url, Headers::new(), params)
             .await?
@@ -281,12 +982,151 @@ impl GoogleMusicApi {
         Ok(track)
     }
 
-    pub async fn get_album(&self, album_id: &str) -> Result<Album, Error> {
+    /**
+     * Sets the rating of a library or store track
+     *
+     * Returns the track with the updated rating.
+     */
+    pub async fn rate_track(
+        &self,
+        track: &Track,
+        rating: TrackRating,
+    ) -> Result<Track, GmusicError> {
+        let mut track = track.clone();
+        if track.id.is_empty() {
+            // store tracks fetched from the catalog are only identified by their store id
+            track.id = track.store_id.clone().ok_or_else(|| {
+                GmusicError::InvalidArgument("Track has neither an id nor a store id".into())
+            })?;
+        }
+        track.rating = Some(rating);
+        self.mutate("trackbatch", vec![TrackMutation::Update(track.clone())])
+            .await?;
+
+        Ok(track)
+    }
+
+    /**
+     * Reports plays of library or store tracks, increasing their play count
+     *
+     * Returns the ids of the tracks the server rejected, the plays of all other tracks were counted.
+     * See [PlayEventQueue](struct.PlayEventQueue.html) to collect events and report them later on.
+     */
+    pub async fn report_plays(&self, events: &[PlayEvent]) -> Result<Vec<String>, GmusicError> {
+        let mut plays: Vec<(&str, Vec<&PlayEvent>)> = Vec::new();
+        for event in events {
+            match plays.iter_mut().find(|(id, _)| *id == event.track_id) {
+                Some((_, track_plays)) => track_plays.push(event),
+                None => plays.push((&event.track_id, vec![event])),
+            }
+        }
+        let track_stats = plays
+            .into_iter()
+            .map(|(id, track_plays)| {
+                let last_played_at = track_plays.iter().map(|e| e.played_at).max().unwrap_or(0);
+                TrackStat {
+                    id: id.to_string(),
+                    incremental_plays: track_plays.len() as u64,
+                    last_play_time_millis: (last_played_at / 1000).to_string(),
+                    track_type: if id.starts_with('T') { 2 } else { 1 },
+                    track_events: track_plays
+                        .iter()
+                        .map(|event| TrackEvent {
+                            context_type: event.context_type,
+                            event_timestamp_micros: event.played_at.to_string(),
+                            // 2 marks a completed playback
+                            event_type: 2,
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        let url = format!("{}trackstats", self.client.base_url);
+        let body = TrackStatsRequest { track_stats };
+        let params = Headers::new().append("alt", "json");
+        let res: TrackStatsResponse = self
+            .api_post(url, &body, Headers::new(), params)
+            .await?
+            .json()
+            .await?;
+
+        let failed = res
+            .responses
+            .into_iter()
+            .filter(|result| result.response_code != "OK")
+            .map(|result| result.id)
+            .collect();
+
+        Ok(failed)
+    }
+
+    /**
+     * Adds the store tracks with the given ids to the library
+     *
+     * Returns the library ids of the added tracks.
+     */
+    pub async fn add_store_tracks(&self, track_ids: &[&str]) -> Result<Vec<String>, GmusicError> {
+        let mut mutations = Vec::with_capacity(track_ids.len());
+        for track_id in track_ids {
+            let track = self.get_store_track(track_id).await?;
+            let create = TrackCreate::new(track_id.to_string(), &track);
+            mutations.push(TrackMutation::Create(create));
+        }
+
+        self.mutate_tracks(mutations).await
+    }
+
+    /**
+     * Adds all tracks of the given store album to the library
+     *
+     * Returns the library ids of the added tracks.
+     */
+    pub async fn add_album_to_library(&self, album_id: &str) -> Result<Vec<String>, GmusicError> {
+        let album = self.get_album(album_id).await?;
+        let mutations = album
+            .tracks
+            .iter()
+            .map(|track| {
+                let store_id = track.store_id.clone().ok_or_else(|| {
+                    GmusicError::InvalidResponse(format!(
+                        "Track {} of album {} has no store id",
+                        track.title, album_id
+                    ))
+                })?;
+                Ok(TrackMutation::Create(TrackCreate::new(store_id, track)))
+            })
+            .collect::<Result<Vec<_>, GmusicError>>()?;
+
+        self.mutate_tracks(mutations).await
+    }
+
+    /**
+     * Removes the tracks with the given library ids from the library
+     *
+     * Returns the ids of the removed tracks.
+     */
+    pub async fn delete_tracks(&self, track_ids: &[&str]) -> Result<Vec<String>, GmusicError> {
+        let mutations = track_ids
+            .iter()
+            .map(|id| TrackMutation::Delete(id.to_string()))
+            .collect();
+
+        self.mutate_tracks(mutations).await
+    }
+
+    async fn mutate_tracks(
+        &self,
+        mutations: Vec<TrackMutation>,
+    ) -> Result<Vec<String>, GmusicError> {
+        self.mutate_ids("trackbatch", mutations, "track").await
+    }
+
+    pub async fn get_album(&self, album_id: &str) -> Result<Album, GmusicError> {
         let params = Headers::new()
             .append("alt", "json")
             .append("nid", album_id)
             .append("include-tracks", "true");
-        let url = format!("{}fetchalbum", BASE_URL);
+        let url = format!("{}fetchalbum", self.client.base_url);
         let album: Album = self
             .api_get(url, Headers::new(), params)
             .await?
@@ -296,13 +1136,13 @@ impl GoogleMusicApi {
         Ok(album)
     }
 
-    pub async fn get_artist(&self, artist_id: &str) -> Result<Artist, Error> {
+    pub async fn get_artist(&self, artist_id: &str) -> Result<Artist, GmusicError> {
         let params = Headers::new()
             .append("alt", "json")
             .append("nid", artist_id)
             .append("include-albums", "true")
             .append("num-top-tracks", "20");
-        let url = format!("{}fetchartist", BASE_URL);
+        let url = format!("{}fetchartist", self.client.base_url);
         let artist: Artist = self
             .api_get(url, Headers::new(), params)
             .await?
@@ -312,12 +1152,165 @@ impl GoogleMusicApi {
         Ok(artist)
     }
 
+    /**
+     * Returns all radio stations of the user
+     */
+    pub async fn get_all_stations(&self) -> Result<Vec<Station>, GmusicError> {
+        GoogleMusicApi::collect_pages(|page| self.get_feed_page("radio/station", page, None, None))
+            .await
+    }
+
+    /**
+     * Creates a radio station for the given seed, returns the id of the station
+     *
+     * If a station with the same seed already exists its id is returned instead.
+     *
+     * # Example
+     * ```rust,no_run
+     * use gmusic::{GoogleMusicApi, StationSeed};
+     *
+     * #[tokio::main]
+     * async fn main() {
+     *   let api = GoogleMusicApi::new(String::new(), String::new(), None).unwrap();
+     *   api.load_token().await.unwrap();
+     *
+     *   let artist = api.get_artist("Aaq2r2ip5a4iw3i3ofndxmn4pha").await.unwrap();
+     *   let station_id = api.create_station(&artist.name, StationSeed::from(&artist)).await.unwrap();
+     *   let tracks = api.get_station_tracks(&station_id, 25, &[]).await.unwrap();
+     * }
+     * ```
+     */
+    pub async fn create_station(
+        &self,
+        name: &str,
+        seed: StationSeed,
+    ) -> Result<String, GmusicError> {
+        let recent_timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros();
+        let station = StationCreate {
+            client_id: Uuid::new_v4().to_string(),
+            deleted: false,
+            image_type: 1,
+            last_modified_timestamp: String::from("-1"),
+            recent_timestamp: recent_timestamp.to_string(),
+            name: name.to_string(),
+            seed,
+            tracks: Vec::new(),
+        };
+        let mut ids = self
+            .mutate_ids(
+                "radio/editstation",
+                vec![StationMutation::create(station)],
+                "station",
+            )
+            .await?;
+
+        Ok(ids.remove(0))
+    }
+
+    /**
+     * Deletes the radio stations with the given ids
+     *
+     * Returns the ids of the deleted stations.
+     */
+    pub async fn delete_stations(&self, station_ids: &[&str]) -> Result<Vec<String>, GmusicError> {
+        let mutations = station_ids
+            .iter()
+            .map(|id| StationMutation::delete(id.to_string()))
+            .collect();
+        self.mutate_ids("radio/editstation", mutations, "station")
+            .await
+    }
+
+    /**
+     * Returns up to `num_tracks` tracks of the given radio station
+     *
+     * Tracks in `recently_played` are avoided by the radio.
+     */
+    pub async fn get_station_tracks(
+        &self,
+        station_id: &str,
+        num_tracks: u32,
+        recently_played: &[&str],
+    ) -> Result<Vec<Track>, GmusicError> {
+        let url = format!("{}radio/stationfeed", self.client.base_url);
+        let body = StationFeedRequest {
+            content_filter: 1,
+            stations: vec![StationFeedFilter {
+                radio_id: station_id.to_string(),
+                num_entries: num_tracks,
+                recently_played: recently_played
+                    .iter()
+                    .map(|id| RecentlyPlayedTrack::new(id))
+                    .collect(),
+            }],
+        };
+        let params = Headers::new().append("alt", "json");
+        let res: StationFeedResponse = self
+            .api_post(url, &body, Headers::new(), params)
+            .await?
+            .json()
+            .await?;
+
+        let tracks = res
+            .data
+            .stations
+            .into_iter()
+            .next()
+            .map(|station| station.tracks)
+            .unwrap_or_default();
+
+        Ok(tracks)
+    }
+
+    /**
+     * Returns up to `num_tracks` tracks of the "I'm Feeling Lucky" radio station
+     *
+     * Tracks in `recently_played` are avoided by the radio.
+     */
+    pub async fn get_lucky_tracks(
+        &self,
+        num_tracks: u32,
+        recently_played: &[&str],
+    ) -> Result<Vec<Track>, GmusicError> {
+        self.get_station_tracks(LUCKY_STATION_ID, num_tracks, recently_played)
+            .await
+    }
+
+    /**
+     * Returns up to `num_tracks` tracks of an instant mix seeded by the given track
+     *
+     * The track can be a library track or a store track as returned by `search` or `get_album`.
+     * The mix is backed by a radio station in the library of the user. An existing station with the
+     * same seed is reused, otherwise a new station is created.
+     */
+    pub async fn get_instant_mix(
+        &self,
+        track: &Track,
+        num_tracks: u32,
+    ) -> Result<Vec<Track>, GmusicError> {
+        let seed = StationSeed::from(track);
+        let existing = self
+            .get_all_stations()
+            .await?
+            .into_iter()
+            .find(|station| station.deleted != Some(true) && station.seed == seed);
+        let station_id = match existing {
+            Some(station) => station.id,
+            None => {
+                let name = format!("{} - {}", track.artist, track.title);
+                self.create_station(&name, seed).await?
+            }
+        };
+
+        self.get_station_tracks(&station_id, num_tracks, &[]).await
+    }
+
     /**
      * Get a stream url for the given track id with the given device id
      *
      * Valid for 1 Minute
      */
-    pub async fn get_stream_url(&self, id: &str, device_id: &str) -> Result<Url, Error> {
+    pub async fn get_stream_url(&self, id: &str, device_id: &str) -> Result<Url, GmusicError> {
         let (sig, salt) = GoogleMusicApi::get_signature(id)?;
         let mut params = Headers::new()
             .append("opt", "hi")
@@ -331,12 +1324,31 @@ impl GoogleMusicApi {
             params = params.append("songid", id);
         }
         let headers = Headers::new().append("X-Device-ID", device_id);
-        let res = self.api_get(STREAM_URL, headers, params).await?;
+        let res = self
+            .api_get(self.client.stream_url.as_str(), headers, params)
+            .await?;
 
         Ok(res.url().clone())
     }
 
-    fn get_signature(id: &str) -> Result<(String, String), Error> {
+    /**
+     * Get a stream url for the given track id with the device id configured in the builder
+     *
+     * Valid for 1 Minute
+     */
+    pub async fn get_track_stream_url(&self, id: &str) -> Result<Url, GmusicError> {
+        let device_id = self
+            .device_id
+            .as_ref()
+            .ok_or_else(|| GmusicError::InvalidArgument("No device id configured".into()))?;
+        self.get_stream_url(id, device_id).await
+    }
+
+    pub fn device_id(&self) -> Option<&str> {
+        self.device_id.as_deref()
+    }
+
+    fn get_signature(id: &str) -> Result<(String, String), GmusicError> {
         let key_1 = base64::decode("VzeC4H4h+T2f0VI180nVX8x+Mb5HiTtGnKgH52Otj8ZCGDz9jRWyHb6QXK0JskSiOgzQfwTY5xgLLSdUSreaLVMsVVWfxfa8Rw==")?;
         let key_2 = base64::decode("ZAPnhUkYwQ6y5DdQxWThbvhJHN8msQ1rqJw0ggKdufQjelrKuiGGJI30aswkgCWTDyHkTGK9ynlqTkJ5L4CiGGUabGeo8M6JTQ==")?;
 
@@ -347,12 +1359,12 @@ impl GoogleMusicApi {
         let salt = format!("{}", salt);
 
         let mut mac = Hmac::<Sha1>::new_varkey(&key)
-            .map_err(|err| format_err!("Invalid key length {:?}", err))?;
+            .map_err(|err| GmusicError::Other(format!("Invalid key length {:?}", err)))?;
         mac.input(id.as_bytes());
         mac.input(salt.as_bytes());
 
         // substitution happens to fix url encoding
-        let signature = base64::encode(&mac.result().code())
+        let signature = base64::encode(mac.result().code())
             .replace("+", "-")
             .replace("/", "_")
             .replace("=", ".");
@@ -364,8 +1376,8 @@ impl GoogleMusicApi {
         &self,
         query: &str,
         max_results: Option<u64>,
-    ) -> Result<Vec<SearchResultCluster>, Error> {
-        let url = format!("{}query", BASE_URL);
+    ) -> Result<Vec<SearchResultCluster>, GmusicError> {
+        let url = format!("{}query", self.client.base_url);
         let max_results = max_results.unwrap_or(50);
         let max_results = format!("{}", max_results);
         let params = Headers::new()
@@ -382,12 +1394,73 @@ impl GoogleMusicApi {
         Ok(res.cluster_detail)
     }
 
+    async fn mutate<M>(
+        &self,
+        endpoint: &str,
+        mutations: Vec<M>,
+    ) -> Result<Vec<MutateResult>, GmusicError>
+    where
+        M: serde::Serialize,
+    {
+        let url = format!("{}{}", self.client.base_url, endpoint);
+        let count = mutations.len();
+        let body = MutateRequest { mutations };
+        let params = Headers::new().append("alt", "json");
+        let res: MutateResponse = self
+            .api_post(url, &body, Headers::new(), params)
+            .await?
+            .json()
+            .await?;
+
+        if res.mutate_response.len() != count {
+            return Err(GmusicError::InvalidResponse(format!(
+                "Expected {} mutate results, got {}",
+                count,
+                res.mutate_response.len()
+            )));
+        }
+        if let Some(failed) = res
+            .mutate_response
+            .iter()
+            .find(|result| result.response_code != "OK")
+        {
+            return Err(GmusicError::MutationFailed {
+                id: failed.id.clone().unwrap_or_else(|| "unknown item".into()),
+                response_code: failed.response_code.clone(),
+            });
+        }
+
+        Ok(res.mutate_response)
+    }
+
+    /**
+     * Applies the mutations and returns the ids of the mutated items, `what` names them in errors
+     */
+    async fn mutate_ids<M>(
+        &self,
+        endpoint: &str,
+        mutations: Vec<M>,
+        what: &str,
+    ) -> Result<Vec<String>, GmusicError>
+    where
+        M: serde::Serialize,
+    {
+        let res = self.mutate(endpoint, mutations).await?;
+        res.into_iter()
+            .map(|result| {
+                result.id.ok_or_else(|| {
+                    GmusicError::InvalidResponse(format!("Missing {} id in mutate response", what))
+                })
+            })
+            .collect()
+    }
+
     async fn api_get<S: Into<String>>(
         &self,
         url: S,
         headers: Headers,
         params: Headers,
-    ) -> Result<Response, Error> {
+    ) -> Result<Response, GmusicError> {
         self.request::<()>(url.into(), Method::GET, None, headers, params)
             .await
     }
@@ -398,7 +1471,7 @@ impl GoogleMusicApi {
         body: &B,
         headers: Headers,
         params: Headers,
-    ) -> Result<Response, Error>
+    ) -> Result<Response, GmusicError>
     where
         B: serde::Serialize,
     {
@@ -406,32 +1479,36 @@ impl GoogleMusicApi {
             .await
     }
 
-    async fn request<'a, B>(
+    async fn request<B>(
         &self,
         url: String,
         method: Method,
         body: Option<&B>,
         headers: Headers,
         params: Headers,
-    ) -> Result<Response, Error>
+    ) -> Result<Response, GmusicError>
     where
         B: serde::Serialize,
     {
-        if self.auth_token.requires_new_token().await {
-            self.auth_token.refresh(&self.client.oauth_client).await?;
-        }
-        let client = reqwest::Client::new();
+        self.auth_token
+            .refresh_if_expiring(
+                &self.client.oauth_client,
+                &self.client.http_client,
+                Duration::new(0, 0),
+            )
+            .await?;
+        let category = self.endpoint_category(&url);
         let mut url = Url::parse(&url)?;
         {
             let mut query_pairs = url.query_pairs_mut();
-            for (key, value) in GoogleMusicApi::default_params() {
+            for (key, value) in self.default_params() {
                 query_pairs.append_pair(key, value);
             }
             for (key, value) in params.into_inner() {
                 query_pairs.append_pair(key, value.as_str());
             }
         }
-        let mut req = client.request(method, url);
+        let mut req = self.client.http_client.request(method, url);
         let mut header_map = HeaderMap::new();
         for (key, value) in headers.into_inner() {
             header_map.insert(key, value.parse()?);
@@ -440,44 +1517,90 @@ impl GoogleMusicApi {
         if let Some(body) = body {
             req = req.json(&body);
         }
-        let res = req
-            .try_clone()
-            .unwrap()
-            .header(AUTHORIZATION, self.auth_token.get_auth_header().await?)
-            .send()
-            .await?
-            .error_for_status();
-        if let Err(err) = res {
-            self.retry_request(req, err).await
-        } else {
-            let res = res.unwrap();
-            Ok(res)
+        self.send_with_retries(req, category).await
+    }
+
+    fn endpoint_category(&self, url: &str) -> EndpointCategory {
+        if url == self.client.stream_url {
+            return EndpointCategory::Stream;
         }
+        let endpoint = url.trim_start_matches(self.client.base_url.as_str());
+        EndpointCategory::from_endpoint(endpoint)
     }
 
-    async fn retry_request(
+    /**
+     * Sends the request until it succeeds or the retry policy gives up
+     *
+     * A rejected token is refreshed once without counting as attempt.
+     */
+    async fn send_with_retries(
         &self,
         req: RequestBuilder,
-        err: reqwest::Error,
-    ) -> Result<Response, Error> {
-        if let Some(StatusCode::UNAUTHORIZED) = err.status() {
-            self.auth_token.refresh(&self.client.oauth_client).await?;
+        category: EndpointCategory,
+    ) -> Result<Response, GmusicError> {
+        let idempotent = category != EndpointCategory::Mutation;
+        let mut attempt = 1;
+        let mut refreshed = false;
+        loop {
+            self.client.rate_limiter.acquire(category).await;
+            let auth_header = self.auth_token.get_auth_header().await?;
             let res = req
-                .header(AUTHORIZATION, self.auth_token.get_auth_header().await?)
+                .try_clone()
+                .unwrap()
+                .header(AUTHORIZATION, auth_header.as_str())
                 .send()
-                .await?
-                .error_for_status()?;
-            Ok(res)
-        } else {
-            Err(err.into())
+                .await;
+            let (err, retry_after) = match res {
+                Ok(res) if res.status().is_success() => return Ok(res),
+                Ok(res) => {
+                    let retry_after = parse_retry_after(res.headers());
+                    (
+                        GmusicError::from_status(res.status(), retry_after),
+                        retry_after,
+                    )
+                }
+                Err(err) => (GmusicError::from(err), None),
+            };
+            if let GmusicError::Unauthorized = err {
+                if !refreshed {
+                    refreshed = true;
+                    self.auth_token
+                        .refresh(
+                            &self.client.oauth_client,
+                            &self.client.http_client,
+                            &auth_header,
+                        )
+                        .await?;
+                    continue;
+                }
+            }
+            let delay =
+                self.client
+                    .retry_policy
+                    .retry_delay(attempt, &err, retry_after, idempotent);
+            match delay {
+                Some(delay) => {
+                    debug!(
+                        "attempt {} failed with {}, retrying in {:?}",
+                        attempt, err, delay
+                    );
+                    delay_for(delay).await;
+                    attempt += 1;
+                }
+                None => return Err(err),
+            }
         }
     }
 
-    fn default_params() -> Vec<(&'static str, &'static str)> {
-        vec![("dv", "0"), ("hl", "en_US"), ("tier", "aa")]
+    fn default_params(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("dv", "0"),
+            ("hl", &self.client.locale),
+            ("tier", &self.client.tier),
+        ]
     }
 
-    pub fn get_device_id_from_mac_address() -> Result<Option<String>, Error> {
+    pub fn get_device_id_from_mac_address() -> Result<Option<String>, GmusicError> {
         let address = mac_address::get_mac_address()?;
 
         Ok(address.map(|addr| addr.to_string().replace(":", "")))
@@ -1,65 +1,520 @@
+use std::error::Error as StdError;
+use std::future::Future;
 use std::io;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
 
-use failure::Error;
+use log::debug;
 use oauth2::basic::{BasicClient, BasicTokenResponse};
-use oauth2::reqwest::async_http_client;
+use oauth2::http::header::HeaderName;
+use oauth2::http::{HeaderMap, HeaderValue, StatusCode};
+use oauth2::reqwest::AsyncHttpClientError;
 use oauth2::{
-    AsyncCodeTokenRequest, AuthorizationCode, CsrfToken, PkceCodeChallenge, PkceCodeVerifier, Scope,
+    AsyncCodeTokenRequest, AuthorizationCode, CsrfToken, HttpRequest, HttpResponse,
+    PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, Scope,
 };
+use serde::{Deserialize, Serialize};
+use tokio::io::{stdin, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::time::{delay_for, timeout};
+use url::Url;
+
+use crate::api::GoogleMusicApiClient;
+use crate::error::GmusicError;
 
 static SCOPE: &str = "https://www.googleapis.com/auth/skyjam";
+static DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+static DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+static REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+
+/// How long the loopback login waits for the redirect
+const LOOPBACK_LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+/// How long a connection to the loopback listener may take to send its request line
+const LOOPBACK_READ_TIMEOUT: Duration = Duration::from_secs(10);
 
 /**
  * Prints the authorize url to stdout and waits for the authorization code from stdin
  */
-pub fn stdio_login(url: String) -> String {
+pub async fn stdio_login(url: String) -> Result<String, io::Error> {
     println!("Open this URL in your browser:\n{}\n", url);
 
     let mut code = String::new();
-    io::stdin().read_line(&mut code).unwrap();
+    BufReader::new(stdin()).read_line(&mut code).await?;
+
+    Ok(code)
+}
+
+/**
+ * Prints the authorize url to stdout, used with [login_loopback](../struct.GoogleMusicApi.html#method.login_loopback)
+ */
+pub fn print_login_url(url: &str) {
+    println!("Open this URL in your browser:\n{}\n", url);
+}
+
+/**
+ * Prints the verification url and user code to stdout, used with [login_device](../struct.GoogleMusicApi.html#method.login_device)
+ */
+pub fn print_device_code(authorization: &DeviceAuthorization) {
+    println!(
+        "Open {} on another device and enter the code {}\n",
+        authorization.verification_url, authorization.user_code
+    );
+}
+
+/**
+ * Code the user has to enter at the verification url to authorize a device login
+ */
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceAuthorization {
+    device_code: String,
+    pub user_code: String,
+    #[serde(alias = "verification_uri")]
+    pub verification_url: String,
+    /// Seconds until the user code expires
+    pub expires_in: u64,
+    /// Seconds to wait between polling for the token
+    #[serde(default = "default_device_interval")]
+    pub interval: u64,
+}
+
+fn default_device_interval() -> u64 {
+    5
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+/**
+ * An OAuth login waiting for the authorization code
+ *
+ * Created by [begin_login](../struct.GoogleMusicApi.html#method.begin_login), the user has to be
+ * redirected to `url`. Can be serialized to keep it in a session until the redirect arrives.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingLogin {
+    // TODO: When url crate version matches we should use the url type
+    pub url: String,
+    /// PKCE code verifier, has to be kept secret
+    pub verifier: String,
+    /// CSRF state which has to be returned by the redirect
+    pub state: String,
+}
 
-    code
+impl PendingLogin {
+    /**
+     * Checks the state returned by the redirect against the state of this login
+     */
+    pub fn verify_state(&self, state: &str) -> Result<(), GmusicError> {
+        if self.state != state {
+            return Err(GmusicError::OAuth(
+                "Authorization state does not match".into(),
+            ));
+        }
+        Ok(())
+    }
 }
 
-// TODO: When url crate version matches we should return the url type
-pub(crate) fn get_oauth_url(client: &BasicClient) -> (String, PkceCodeVerifier) {
+pub(crate) fn get_oauth_url(client: &BasicClient) -> PendingLogin {
     let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
 
-    let (authorize_url, _) = client
+    let (authorize_url, csrf_state) = client
         .authorize_url(CsrfToken::new_random)
         .add_scope(Scope::new(SCOPE.to_string()))
         .set_pkce_challenge(pkce_code_challenge)
         .url();
 
-    (authorize_url.to_string(), pkce_code_verifier)
+    PendingLogin {
+        url: authorize_url.to_string(),
+        verifier: pkce_code_verifier.secret().clone(),
+        state: csrf_state.secret().clone(),
+    }
+}
+
+pub(crate) async fn complete_login(
+    client: &BasicClient,
+    http_client: &reqwest::Client,
+    pending: PendingLogin,
+    code: String,
+    state: &str,
+) -> Result<BasicTokenResponse, GmusicError> {
+    pending.verify_state(state)?;
+
+    let verifier = PkceCodeVerifier::new(pending.verifier);
+    request_token(client, http_client, code, verifier).await
 }
 
 pub(crate) async fn request_token(
     client: &BasicClient,
+    http_client: &reqwest::Client,
     code: String,
     verifier: PkceCodeVerifier,
-) -> Result<BasicTokenResponse, Error> {
+) -> Result<BasicTokenResponse, GmusicError> {
     let code = AuthorizationCode::new(code);
 
     let token = client
         .exchange_code(code)
         .set_pkce_verifier(verifier)
-        .request_async(async_http_client)
-        .await?;
+        .request_async(|request| send_oauth_request(http_client, request))
+        .await
+        .map_err(GmusicError::from)?;
 
     Ok(token)
 }
 
-pub(crate) async fn perform_oauth<H>(
+/**
+ * Sends a request of the oauth2 crate with the configured http client
+ *
+ * oauth2 uses the types of http 0.1 while reqwest uses http 0.2, the request and response are
+ * converted between both. Only the fixed google token endpoints are requested, so following
+ * redirects like the configured client does is safe.
+ */
+pub(crate) async fn send_oauth_request(
+    http_client: &reqwest::Client,
+    request: HttpRequest,
+) -> Result<HttpResponse, AsyncHttpClientError> {
+    let method = reqwest::Method::from_bytes(request.method.as_str().as_bytes())
+        .map_err(|err| AsyncHttpClientError::Other(err.to_string()))?;
+    let mut request_builder = http_client
+        .request(method, request.url.as_str())
+        .body(request.body);
+    for (name, value) in &request.headers {
+        request_builder = request_builder.header(name.as_str(), value.as_bytes());
+    }
+    let response = request_builder
+        .send()
+        .await
+        .map_err(AsyncHttpClientError::Reqwest)?;
+
+    let status_code = StatusCode::from_u16(response.status().as_u16())
+        .map_err(|err| AsyncHttpClientError::Other(err.to_string()))?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in response.headers() {
+        let name = HeaderName::from_bytes(name.as_str().as_bytes())
+            .map_err(|err| AsyncHttpClientError::Other(err.to_string()))?;
+        let value = HeaderValue::from_bytes(value.as_bytes())
+            .map_err(|err| AsyncHttpClientError::Other(err.to_string()))?;
+        headers.append(name, value);
+    }
+    let body = response
+        .bytes()
+        .await
+        .map_err(AsyncHttpClientError::Reqwest)?;
+
+    Ok(HttpResponse {
+        status_code,
+        headers,
+        body: body.to_vec(),
+    })
+}
+
+pub(crate) async fn perform_oauth<H, F, E>(
     client: &BasicClient,
+    http_client: &reqwest::Client,
     handler: H,
-) -> Result<BasicTokenResponse, Error>
+) -> Result<BasicTokenResponse, GmusicError>
 where
-    H: Fn(String) -> String,
+    H: FnOnce(String) -> F,
+    F: Future<Output = Result<String, E>>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
 {
-    let (authorize_url, pkce_code_verifier) = get_oauth_url(client);
+    let pending = get_oauth_url(client);
+
+    let code = handler(pending.url)
+        .await
+        .map_err(|err| GmusicError::LoginHandler(err.into()))?;
+    let code = code.trim().to_string();
+    let verifier = PkceCodeVerifier::new(pending.verifier);
+
+    request_token(client, http_client, code, verifier).await
+}
+
+/**
+ * Listens on a random port on 127.0.0.1 and uses it as redirect url to receive the authorization code
+ *
+ * Connections which don't send a redirect in time are skipped, the login fails after 5 minutes.
+ */
+pub(crate) async fn perform_loopback_oauth<H>(
+    client: &BasicClient,
+    http_client: &reqwest::Client,
+    handler: H,
+) -> Result<BasicTokenResponse, GmusicError>
+where
+    H: Fn(&str),
+{
+    let mut listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let redirect_url = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+    let client = client
+        .clone()
+        .set_redirect_url(RedirectUrl::new(redirect_url)?);
+
+    let pending = get_oauth_url(&client);
+
+    handler(&pending.url);
+
+    let redirect = async {
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let (reader, mut writer) = stream.split();
+            let mut request_line = String::new();
+            let read = timeout(
+                LOOPBACK_READ_TIMEOUT,
+                BufReader::new(reader).read_line(&mut request_line),
+            )
+            .await;
+            if !matches!(read, Ok(Ok(_))) {
+                debug!("ignoring connection without request");
+                continue;
+            }
+
+            let params = match parse_redirect_request(&request_line) {
+                Ok(Some(params)) => params,
+                _ => {
+                    debug!(
+                        "ignoring request without oauth parameters: {}",
+                        request_line.trim()
+                    );
+                    let response =
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+                    if let Err(err) = writer.write_all(response.as_bytes()).await {
+                        debug!("unable to answer request: {}", err);
+                    }
+                    continue;
+                }
+            };
+            let result = verify_redirect_params(params, &pending);
+            let body = match result {
+                Ok(_) => "Login successful, you can close this window.",
+                Err(_) => "Login failed, please check the application for details.",
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            if let Err(err) = writer.write_all(response.as_bytes()).await {
+                debug!("unable to answer redirect: {}", err);
+            }
+            break result;
+        }
+    };
+    let code = timeout(LOOPBACK_LOGIN_TIMEOUT, redirect)
+        .await
+        .map_err(|_| GmusicError::OAuth("Timed out waiting for the login redirect".into()))??;
+
+    let verifier = PkceCodeVerifier::new(pending.verifier);
+
+    request_token(&client, http_client, code, verifier).await
+}
+
+struct RedirectParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+fn parse_redirect_request(request_line: &str) -> Result<Option<RedirectParams>, GmusicError> {
+    let mut parts = request_line.split_whitespace();
+    let target = match (parts.next(), parts.next(), parts.next()) {
+        (Some(_), Some(target), Some(version))
+            if target.starts_with('/') && version.starts_with("HTTP/") =>
+        {
+            target
+        }
+        _ => return Err(GmusicError::OAuth("Invalid redirect request".into())),
+    };
+    let url = Url::parse(&format!("http://127.0.0.1{}", target))?;
+    let mut params = RedirectParams {
+        code: None,
+        state: None,
+        error: None,
+    };
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "code" => params.code = Some(value.into_owned()),
+            "state" => params.state = Some(value.into_owned()),
+            "error" => params.error = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    if params.code.is_none() && params.error.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(params))
+}
+
+fn verify_redirect_params(
+    params: RedirectParams,
+    pending: &PendingLogin,
+) -> Result<String, GmusicError> {
+    if let Some(error) = params.error {
+        return Err(GmusicError::OAuth(error));
+    }
+    pending.verify_state(params.state.as_deref().unwrap_or_default())?;
+    params
+        .code
+        .ok_or_else(|| GmusicError::OAuth("Missing authorization code".into()))
+}
+
+/**
+ * Requests a user code and polls the token endpoint until the user authorized the device
+ */
+pub(crate) async fn perform_device_oauth<H>(
+    client: &GoogleMusicApiClient,
+    handler: H,
+) -> Result<BasicTokenResponse, GmusicError>
+where
+    H: Fn(&DeviceAuthorization),
+{
+    // oauth2 3.0 only implements the code, password and client credentials grants and can't send
+    // other grant types, so the device code grant (RFC 8628) is sent by hand. The token endpoint
+    // and credentials are the same the oauth client was configured with.
+    let http_client = &client.http_client;
+    let authorization: DeviceAuthorization = http_client
+        .post(DEVICE_CODE_URL)
+        .form(&[("client_id", client.id.as_str()), ("scope", SCOPE)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    handler(&authorization);
+
+    let expires_at = Instant::now() + Duration::from_secs(authorization.expires_in);
+    let mut interval = Duration::from_secs(authorization.interval);
+    loop {
+        delay_for(interval).await;
+        if Instant::now() >= expires_at {
+            return Err(GmusicError::OAuth("Device code expired".into()));
+        }
+
+        let res = http_client
+            .post(client.token_url.as_str())
+            .basic_auth(&client.id, Some(&client.secret))
+            .form(&[
+                ("device_code", authorization.device_code.as_str()),
+                ("grant_type", DEVICE_GRANT_TYPE),
+            ])
+            .send()
+            .await?;
+        if res.status().is_success() {
+            return Ok(res.json().await?);
+        }
+        let err: TokenError = res.json().await?;
+        match err.error.as_str() {
+            "authorization_pending" => debug!("waiting for device authorization"),
+            "slow_down" => interval += Duration::from_secs(5),
+            _ => {
+                return Err(GmusicError::OAuth(
+                    err.error_description.unwrap_or(err.error),
+                ))
+            }
+        }
+    }
+}
+
+/**
+ * Revokes the given refresh or access token, tokens which are already invalid are ignored
+ */
+pub(crate) async fn revoke_token(
+    http_client: &reqwest::Client,
+    token: &str,
+) -> Result<(), GmusicError> {
+    let res = http_client
+        .post(REVOKE_URL)
+        .form(&[("token", token)])
+        .send()
+        .await?;
+    if res.status().is_success() {
+        return Ok(());
+    }
+    let status = res.status();
+    let err: TokenError = res.json().await?;
+    if err.error == "invalid_token" {
+        debug!("token was already revoked");
+        return Ok(());
+    }
+    Err(GmusicError::OAuth(format!(
+        "Revoking token failed with {}: {}",
+        status,
+        err.error_description.unwrap_or(err.error)
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending_login() -> PendingLogin {
+        PendingLogin {
+            url: "https://accounts.google.com/o/oauth2/auth".into(),
+            verifier: "verifier".into(),
+            state: "expected-state".into(),
+        }
+    }
+
+    fn redirect(request_line: &str) -> RedirectParams {
+        parse_redirect_request(request_line)
+            .expect("request line should be valid")
+            .expect("request line should contain oauth parameters")
+    }
+
+    #[test]
+    fn accepts_matching_state() {
+        let params = redirect("GET /?code=4%2Fabc&state=expected-state HTTP/1.1\r\n");
+
+        let code = verify_redirect_params(params, &pending_login()).unwrap();
+
+        assert_eq!(code, "4/abc");
+    }
+
+    #[test]
+    fn rejects_mismatched_state() {
+        let params = redirect("GET /?code=abc&state=other-state HTTP/1.1\r\n");
+
+        let result = verify_redirect_params(params, &pending_login());
+
+        assert!(matches!(result, Err(GmusicError::OAuth(_))));
+        assert!(pending_login().verify_state("other-state").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_state() {
+        let params = redirect("GET /?code=abc HTTP/1.1\r\n");
+
+        let result = verify_redirect_params(params, &pending_login());
+
+        assert!(matches!(result, Err(GmusicError::OAuth(_))));
+    }
+
+    #[test]
+    fn returns_redirect_error() {
+        let params = redirect("GET /?error=access_denied&state=expected-state HTTP/1.1\r\n");
+
+        let result = verify_redirect_params(params, &pending_login());
+
+        assert!(matches!(result, Err(GmusicError::OAuth(ref error)) if error == "access_denied"));
+    }
 
-    let code = handler(authorize_url);
+    #[test]
+    fn ignores_request_without_query() {
+        assert!(parse_redirect_request("GET /favicon.ico HTTP/1.1\r\n")
+            .unwrap()
+            .is_none());
+        assert!(parse_redirect_request("GET / HTTP/1.1\r\n")
+            .unwrap()
+            .is_none());
+    }
 
-    request_token(client, code, pkce_code_verifier).await
+    #[test]
+    fn rejects_non_http_request() {
+        assert!(parse_redirect_request("").is_err());
+        assert!(parse_redirect_request("hello\r\n").is_err());
+        assert!(parse_redirect_request("SSH-2.0-OpenSSH_8.2\r\n").is_err());
+        assert!(parse_redirect_request("GET example.com HTTP/1.1\r\n").is_err());
+        assert!(parse_redirect_request("GET /?code=abc SMTP\r\n").is_err());
+    }
 }
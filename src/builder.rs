@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use oauth2::basic::BasicClient;
+use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl};
+use url::Url;
+
+use crate::api::{GoogleMusicApiClient, CODE_REDIRECT_URI};
+use crate::error::GmusicError;
+use crate::token::AuthToken;
+use crate::token_store::FileTokenStore;
+use crate::{GoogleMusicApi, HttpClientConfig, RateLimiter, RetryPolicy};
+
+static DEFAULT_BASE_URL: &str = "https://mclients.googleapis.com/sj/v2.5/";
+static DEFAULT_STREAM_URL: &str = "https://mclients.googleapis.com/music/mplay";
+static DEFAULT_LOCALE: &str = "en_US";
+static DEFAULT_TIER: &str = "aa";
+static AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+static TOKEN_URL: &str = "https://www.googleapis.com/oauth2/v3/token";
+
+/**
+ * Configures a [GoogleMusicApi](struct.GoogleMusicApi.html)
+ *
+ * # Example
+ * ```rust,no_run
+ * use gmusic::GoogleMusicApi;
+ *
+ * let api = GoogleMusicApi::builder(String::new(), String::new())
+ *   .with_base_url("http://127.0.0.1:8080/sj/v2.5/")
+ *   .with_locale("de_DE")
+ *   .with_device_id("0123456789abcdef")
+ *   .build()
+ *   .unwrap();
+ * ```
+ */
+#[derive(Debug, Clone)]
+pub struct GoogleMusicApiBuilder {
+    client_id: String,
+    client_secret: String,
+    redirect_uri: Option<String>,
+    base_url: String,
+    stream_url: String,
+    locale: String,
+    tier: String,
+    device_id: Option<String>,
+    http_client: Option<reqwest::Client>,
+    http_config: Option<HttpClientConfig>,
+    retry_policy: RetryPolicy,
+    rate_limiter: RateLimiter,
+}
+
+impl GoogleMusicApiBuilder {
+    pub fn new(client_id: String, client_secret: String) -> GoogleMusicApiBuilder {
+        GoogleMusicApiBuilder {
+            client_id,
+            client_secret,
+            redirect_uri: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            stream_url: DEFAULT_STREAM_URL.to_string(),
+            locale: DEFAULT_LOCALE.to_string(),
+            tier: DEFAULT_TIER.to_string(),
+            device_id: None,
+            http_client: None,
+            http_config: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: RateLimiter::new(),
+        }
+    }
+
+    /**
+     * Defaults to the out of band redirect which displays the code to the user
+     */
+    pub fn with_redirect_uri<S: Into<String>>(mut self, redirect_uri: S) -> Self {
+        self.redirect_uri = Some(redirect_uri.into());
+        self
+    }
+
+    /**
+     * Url all api endpoints are resolved against, defaults to `https://mclients.googleapis.com/sj/v2.5/`
+     */
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /**
+     * Url stream urls are requested from, defaults to `https://mclients.googleapis.com/music/mplay`
+     */
+    pub fn with_stream_url<S: Into<String>>(mut self, stream_url: S) -> Self {
+        self.stream_url = stream_url.into();
+        self
+    }
+
+    /**
+     * Locale of the returned metadata, defaults to `en_US`
+     */
+    pub fn with_locale<S: Into<String>>(mut self, locale: S) -> Self {
+        self.locale = locale.into();
+        self
+    }
+
+    /**
+     * Subscription tier, defaults to `aa` (all access)
+     */
+    pub fn with_tier<S: Into<String>>(mut self, tier: S) -> Self {
+        self.tier = tier.into();
+        self
+    }
+
+    /**
+     * Device id used to request stream urls
+     */
+    pub fn with_device_id<S: Into<String>>(mut self, device_id: S) -> Self {
+        self.device_id = Some(device_id.into());
+        self
+    }
+
+    /**
+     * Uses the given http client, takes precedence over `with_http_config`
+     */
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Self {
+        self.http_config = Some(config);
+        self
+    }
+
+    /**
+     * Configures how failed requests are retried, defaults to [RetryPolicy::default](struct.RetryPolicy.html#impl-Default)
+     */
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /**
+     * Limits how many requests are sent, no limits are applied by default
+     */
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = limiter;
+        self
+    }
+
+    pub fn build(self) -> Result<GoogleMusicApi, GmusicError> {
+        let oauth_client = BasicClient::new(
+            ClientId::new(self.client_id.clone()),
+            Some(ClientSecret::new(self.client_secret.clone())),
+            AuthUrl::new(AUTH_URL.to_string())?,
+            Some(TokenUrl::new(TOKEN_URL.to_string())?),
+        )
+        .set_redirect_url(RedirectUrl::new(
+            self.redirect_uri
+                .unwrap_or_else(|| CODE_REDIRECT_URI.to_string()),
+        )?);
+        let http_client = match (self.http_client, self.http_config) {
+            (Some(client), _) => client,
+            (None, Some(config)) => config.build()?,
+            (None, None) => reqwest::Client::new(),
+        };
+        // endpoints are appended to the base url
+        let mut base_url: String = Url::parse(&self.base_url)?.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        let stream_url: String = Url::parse(&self.stream_url)?.into();
+
+        Ok(GoogleMusicApi {
+            client: GoogleMusicApiClient {
+                id: self.client_id,
+                secret: self.client_secret,
+                oauth_client,
+                token_url: TOKEN_URL.to_string(),
+                http_client,
+                base_url,
+                stream_url,
+                locale: self.locale,
+                tier: self.tier,
+                retry_policy: self.retry_policy,
+                rate_limiter: self.rate_limiter,
+            },
+            auth_token: AuthToken::new(),
+            device_id: self.device_id,
+            token_store: Arc::new(FileTokenStore::default()),
+        })
+    }
+}
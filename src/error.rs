@@ -0,0 +1,130 @@
+use std::error::Error as StdError;
+use std::io;
+use std::time::{Duration, SystemTimeError};
+
+use oauth2::basic::BasicErrorResponse;
+use oauth2::reqwest::{AsyncHttpClientError, Error as OAuthHttpError};
+use oauth2::RequestTokenError;
+use reqwest::StatusCode;
+use thiserror::Error;
+
+/**
+ * Errors returned by the api
+ *
+ * `NotLoggedIn` and `Unauthorized` usually require the user to login again.
+ */
+#[derive(Debug, Error)]
+pub enum GmusicError {
+    /// No token is available or it can not be refreshed
+    #[error("Not logged in")]
+    NotLoggedIn,
+    /// The server rejected the token (HTTP 401)
+    #[error("Unauthorized")]
+    Unauthorized,
+    /// The account is not allowed to access the resource, e.g. without subscription (HTTP 403)
+    #[error("Forbidden")]
+    Forbidden,
+    /// The requested item does not exist (HTTP 404)
+    #[error("Not found")]
+    NotFound,
+    /// Too many requests were sent (HTTP 429)
+    #[error("Rate limited")]
+    RateLimited { retry_after: Option<Duration> },
+    /// Any other unsuccessful HTTP status
+    #[error("Request failed with status {0}")]
+    Status(StatusCode),
+    #[error("Invalid id {0}")]
+    InvalidId(String),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    /// The response could not be decoded
+    #[error("Invalid json: {0}")]
+    Json(#[source] Box<dyn StdError + Send + Sync>),
+    /// The response was decoded but misses expected data
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+    /// The server did not apply a mutation
+    #[error("Mutation of {id} failed with {response_code}")]
+    MutationFailed { id: String, response_code: String },
+    /// Connecting to the server or reading the response failed
+    #[error("Request failed: {0}")]
+    Http(#[source] reqwest::Error),
+    #[error("Authorization failed: {0}")]
+    OAuth(String),
+    /// The authorization server rejected the request, e.g. with `invalid_grant` when the login was revoked
+    #[error("Authorization failed: {0}")]
+    OAuthResponse(BasicErrorResponse),
+    /// The authorization request could not be built
+    #[error("Invalid authorization request: {0}")]
+    OAuthRequest(#[source] oauth2::http::Error),
+    /// The login handler returned an error, e.g. because the user cancelled the login
+    #[error("Login aborted: {0}")]
+    LoginHandler(#[source] Box<dyn StdError + Send + Sync>),
+    #[error("Token store failed: {0}")]
+    TokenStore(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Url(#[from] url::ParseError),
+    #[error("System time is before the unix epoch")]
+    SystemTime(#[from] SystemTimeError),
+    #[error("Invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Reading the mac address failed: {0}")]
+    MacAddress(#[from] mac_address::MacAddressError),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl GmusicError {
+    /**
+     * Maps an unsuccessful HTTP status to the matching error
+     */
+    pub(crate) fn from_status(status: StatusCode, retry_after: Option<Duration>) -> GmusicError {
+        match status {
+            StatusCode::UNAUTHORIZED => GmusicError::Unauthorized,
+            StatusCode::FORBIDDEN => GmusicError::Forbidden,
+            StatusCode::NOT_FOUND => GmusicError::NotFound,
+            StatusCode::TOO_MANY_REQUESTS => GmusicError::RateLimited { retry_after },
+            status => GmusicError::Status(status),
+        }
+    }
+}
+
+impl From<reqwest::Error> for GmusicError {
+    fn from(err: reqwest::Error) -> Self {
+        match err.status() {
+            Some(status) => GmusicError::from_status(status, None),
+            None if err.is_decode() => GmusicError::Json(Box::new(err)),
+            None => GmusicError::Http(err),
+        }
+    }
+}
+
+impl From<RequestTokenError<AsyncHttpClientError, BasicErrorResponse>> for GmusicError {
+    fn from(err: RequestTokenError<AsyncHttpClientError, BasicErrorResponse>) -> Self {
+        match err {
+            RequestTokenError::ServerResponse(response) => GmusicError::OAuthResponse(response),
+            RequestTokenError::Request(OAuthHttpError::Reqwest(err)) => GmusicError::from(err),
+            RequestTokenError::Request(OAuthHttpError::Io(err)) => GmusicError::Io(err),
+            RequestTokenError::Request(OAuthHttpError::Http(err)) => GmusicError::OAuthRequest(err),
+            RequestTokenError::Request(OAuthHttpError::Other(message)) => {
+                GmusicError::OAuth(message)
+            }
+            RequestTokenError::Parse(err, _) => GmusicError::from(err),
+            RequestTokenError::Other(message) => GmusicError::OAuth(message),
+        }
+    }
+}
+
+impl From<serde_json::Error> for GmusicError {
+    fn from(err: serde_json::Error) -> Self {
+        GmusicError::Json(Box::new(err))
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderValue> for GmusicError {
+    fn from(err: reqwest::header::InvalidHeaderValue) -> Self {
+        GmusicError::InvalidArgument(err.to_string())
+    }
+}
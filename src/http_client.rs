@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use reqwest::{Client, Proxy};
+
+use crate::error::GmusicError;
+
+/**
+ * Configuration of the http client shared by all clones and sessions of an api
+ *
+ * # Example
+ * ```rust,no_run
+ * use std::time::Duration;
+ * use gmusic::{GoogleMusicApi, HttpClientConfig};
+ *
+ * let config = HttpClientConfig::new()
+ *   .with_timeout(Duration::from_secs(30))
+ *   .with_user_agent("my-indexer/1.0")
+ *   .with_pool_max_idle_per_host(32);
+ * let api = GoogleMusicApi::new(String::new(), String::new(), None)
+ *   .unwrap()
+ *   .with_http_config(config)
+ *   .unwrap();
+ * ```
+ */
+#[derive(Debug, Clone, Default)]
+pub struct HttpClientConfig {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+    user_agent: Option<String>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+}
+
+impl HttpClientConfig {
+    pub fn new() -> HttpClientConfig {
+        HttpClientConfig::default()
+    }
+
+    /**
+     * Timeout of a whole request, including reading the response body
+     */
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /**
+     * Routes all requests through the given proxy instead of the one configured in the environment
+     */
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    pub fn with_user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /**
+     * Maximum number of idle keep-alive connections kept open per host
+     */
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /**
+     * How long idle keep-alive connections are kept open
+     */
+    pub fn with_pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> Result<Client, GmusicError> {
+        let mut builder = Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(user_agent) = self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+
+        Ok(builder.build()?)
+    }
+}
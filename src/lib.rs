@@ -1,12 +1,34 @@
+pub use crate::accounts::AccountManager;
 pub use crate::api::GoogleMusicApi;
+pub use crate::builder::GoogleMusicApiBuilder;
+pub use crate::error::GmusicError;
+pub use crate::http_client::HttpClientConfig;
 pub use crate::models::album::Album;
 pub use crate::models::all_playlists::{Playlist, PlaylistShareState, PlaylistType};
 pub use crate::models::all_tracks::{Track, TrackRating};
 pub use crate::models::artist::Artist;
 pub use crate::models::playlist_entries::PlaylistEntry;
 pub use crate::models::search_results::SearchResult;
+pub use crate::models::station::{Station, StationSeed, StationSeedType};
+pub use crate::models::Page;
+pub use crate::play_events::{PlayEvent, PlayEventQueue, DEFAULT_PLAY_CONTEXT};
+pub use crate::rate_limit::{EndpointCategory, RateLimit, RateLimiter};
+pub use crate::retry::RetryPolicy;
+pub use crate::sync::{Changes, LibraryChanges, LibrarySyncState};
+pub use crate::token::BackgroundRefresh;
+pub use crate::token_store::TokenStore;
+pub use reqwest::{Proxy, StatusCode};
 
+mod accounts;
 mod api;
 pub mod auth;
+mod builder;
+mod error;
+mod http_client;
 mod models;
+mod play_events;
+mod rate_limit;
+mod retry;
+mod sync;
 mod token;
+pub mod token_store;
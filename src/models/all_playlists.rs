@@ -1,13 +1,6 @@
 use serde::{Deserialize, Serialize};
 
 use crate::models::image_ref::ImageRef;
-use crate::models::GMusicListResponse;
-
-#[derive(Debug, Serialize)]
-pub struct GetAllPlaylistsRequest;
-
-pub type GetAllPlaylistsResponse = GMusicListResponse<Playlist>;
-
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Playlist {
@@ -32,7 +25,7 @@ pub struct Playlist {
     pub content_type: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PlaylistType {
     Magic,
@@ -40,15 +33,9 @@ pub enum PlaylistType {
     UserGenerated,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PlaylistShareState {
     Private,
     Public,
 }
-
-impl GetAllPlaylistsRequest {
-    pub fn new() -> GetAllPlaylistsRequest {
-        GetAllPlaylistsRequest
-    }
-}
@@ -1,13 +1,6 @@
 use serde::{Deserialize, Serialize};
 
 use crate::models::image_ref::ImageRef;
-use crate::models::GMusicListResponse;
-
-#[derive(Debug, Serialize)]
-pub struct GetAllTracksRequest;
-
-pub type GetAllTracksResponse = GMusicListResponse<Track>;
-
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Track {
@@ -39,7 +32,11 @@ pub struct Track {
     pub genre: Option<String>,
     #[serde(default)]
     pub play_count: u64,
-    pub rating: Option<TrackRating>
+    pub rating: Option<TrackRating>,
+    #[serde(default)]
+    pub deleted: bool,
+    pub creation_timestamp: Option<String>,
+    pub last_modified_timestamp: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
@@ -51,9 +48,3 @@ pub enum TrackRating {
     #[serde(rename = "1")]
     Dislike
 }
-
-impl GetAllTracksRequest {
-    pub fn new() -> GetAllTracksRequest {
-        GetAllTracksRequest
-    }
-}
@@ -0,0 +1,57 @@
+use serde::Serialize;
+
+use crate::models::station::StationSeed;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationMutation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_or_get: Option<StationCreate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete: Option<String>,
+    pub include_feed: bool,
+    pub num_entries: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<StationParams>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationCreate {
+    pub client_id: String,
+    pub deleted: bool,
+    pub image_type: u32,
+    pub last_modified_timestamp: String,
+    pub recent_timestamp: String,
+    pub name: String,
+    pub seed: StationSeed,
+    pub tracks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationParams {
+    pub content_filter: u32,
+}
+
+impl StationMutation {
+    pub fn create(station: StationCreate) -> StationMutation {
+        StationMutation {
+            create_or_get: Some(station),
+            delete: None,
+            include_feed: false,
+            num_entries: 0,
+            params: Some(StationParams { content_filter: 1 }),
+        }
+    }
+
+    pub fn delete(station_id: String) -> StationMutation {
+        StationMutation {
+            create_or_get: None,
+            delete: Some(station_id),
+            include_feed: false,
+            num_entries: 0,
+            params: None,
+        }
+    }
+}
@@ -5,14 +5,23 @@ pub mod all_playlists;
 pub mod all_tracks;
 pub mod artist;
 pub mod device_management_info;
+pub mod edit_station;
 pub mod image_ref;
+pub mod playlist_batch;
 pub mod playlist_entries;
+pub mod playlist_entries_batch;
 pub mod search_results;
 pub mod shared_playlist_entries;
+pub mod station;
+pub mod station_feed;
+pub mod track_batch;
+pub mod track_stats;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct GMusicResponse<T> {
+    // feeds filtered by `updated-min` omit the data when nothing changed
+    #[serde(default)]
     pub data: T,
     pub kind: String,
     pub next_page_token: Option<String>,
@@ -25,3 +34,61 @@ pub type GMusicListResponse<T> = GMusicResponse<GMusicList<T>>;
 pub struct GMusicList<T> {
     pub items: Vec<T>,
 }
+
+impl<T> Default for GMusicList<T> {
+    fn default() -> Self {
+        GMusicList { items: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct PageRequest {
+    pub start_token: Option<String>,
+    pub max_results: Option<String>,
+}
+
+impl PageRequest {
+    pub fn new(start_token: Option<String>, max_results: Option<u32>) -> PageRequest {
+        PageRequest {
+            start_token,
+            max_results: max_results.map(|max_results| max_results.to_string()),
+        }
+    }
+}
+
+/// A single page of a paginated feed
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Token to pass as `start_token` to fetch the following page, `None` on the last page
+    pub next_page_token: Option<String>,
+}
+
+impl<T> From<GMusicListResponse<T>> for Page<T> {
+    fn from(res: GMusicListResponse<T>) -> Self {
+        Page {
+            items: res.data.items,
+            next_page_token: res.next_page_token,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MutateRequest<T> {
+    pub mutations: Vec<T>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MutateResponse {
+    #[serde(default)]
+    pub mutate_response: Vec<MutateResult>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MutateResult {
+    pub id: Option<String>,
+    #[allow(dead_code)]
+    pub client_id: Option<String>,
+    pub response_code: String,
+}
@@ -0,0 +1,99 @@
+use serde::Serialize;
+
+use crate::models::all_playlists::{Playlist, PlaylistShareState, PlaylistType};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaylistMutation {
+    Create(PlaylistCreate),
+    Update(PlaylistUpdate),
+    Delete(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistCreate {
+    pub creation_timestamp: String,
+    pub last_modified_timestamp: String,
+    pub deleted: bool,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub playlist_type: PlaylistType,
+    pub share_state: PlaylistShareState,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistUpdate {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_state: Option<PlaylistShareState>,
+}
+
+impl PlaylistCreate {
+    pub fn new(
+        name: String,
+        description: Option<String>,
+        share_state: PlaylistShareState,
+    ) -> PlaylistCreate {
+        PlaylistCreate {
+            creation_timestamp: String::from("-1"),
+            last_modified_timestamp: String::from("0"),
+            deleted: false,
+            name,
+            description,
+            playlist_type: PlaylistType::UserGenerated,
+            share_state,
+        }
+    }
+
+    /**
+     * Builds the created playlist from the id the server assigned
+     *
+     * `timestamp` is the time of the mutation in microseconds since the unix epoch.
+     */
+    pub fn into_playlist(self, id: String, timestamp: String) -> Playlist {
+        Playlist {
+            id,
+            name: self.name,
+            deleted: Some(false),
+            playlist_type: self.playlist_type,
+            last_modified_timestamp: Some(timestamp.clone()),
+            recent_timestamp: Some(timestamp.clone()),
+            share_token: String::new(),
+            owner_profile_photo_url: None,
+            owner_name: None,
+            access_controlled: None,
+            share_state: Some(self.share_state),
+            creation_timestamp: Some(timestamp),
+            album_art_ref: Vec::new(),
+            description: self.description,
+            explicit_type: None,
+            content_type: None,
+        }
+    }
+}
+
+impl PlaylistUpdate {
+    /**
+     * Applies the changed fields to the given playlist
+     */
+    pub fn apply(self, playlist: &mut Playlist, timestamp: String) {
+        if let Some(name) = self.name {
+            playlist.name = name;
+        }
+        if self.description.is_some() {
+            playlist.description = self.description;
+        }
+        if self.share_state.is_some() {
+            playlist.share_state = self.share_state;
+        }
+        playlist.last_modified_timestamp = Some(timestamp);
+    }
+}
@@ -1,10 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::models::GMusicListResponse;
+use crate::models::PageRequest;
 use crate::Track;
 
-pub type GetPlaylistEntriesResponse = GMusicListResponse<PlaylistEntry>;
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaylistEntry {
@@ -22,9 +20,4 @@ pub struct PlaylistEntry {
     pub track: Option<Track>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "kebab-case")]
-pub struct GetPlaylistEntriesRequest {
-    pub start_token: Option<String>,
-    pub max_results: Option<String>,
-}
+pub type GetPlaylistEntriesRequest = PageRequest;
@@ -0,0 +1,86 @@
+use serde::Serialize;
+
+use crate::models::playlist_entries::PlaylistEntry;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaylistEntryMutation {
+    Create(PlaylistEntryCreate),
+    Update(PlaylistEntryUpdate),
+    Delete(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistEntryCreate {
+    pub client_id: String,
+    pub creation_timestamp: String,
+    pub last_modified_timestamp: String,
+    pub deleted: bool,
+    pub playlist_id: String,
+    pub track_id: String,
+    /// 1 for library tracks, 2 for store tracks
+    pub source: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preceding_entry_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub following_entry_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistEntryUpdate {
+    pub id: String,
+    pub client_id: String,
+    pub creation_timestamp: String,
+    pub last_modified_timestamp: String,
+    pub deleted: bool,
+    pub playlist_id: String,
+    pub track_id: String,
+    pub absolute_position: String,
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preceding_entry_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub following_entry_id: Option<String>,
+}
+
+impl PlaylistEntryCreate {
+    pub fn new(client_id: String, playlist_id: String, track_id: String) -> PlaylistEntryCreate {
+        let source = if track_id.starts_with('T') { 2 } else { 1 };
+        PlaylistEntryCreate {
+            client_id,
+            creation_timestamp: String::from("-1"),
+            last_modified_timestamp: String::from("0"),
+            deleted: false,
+            playlist_id,
+            track_id,
+            source,
+            preceding_entry_id: None,
+            following_entry_id: None,
+        }
+    }
+}
+
+impl PlaylistEntryUpdate {
+    /// Moves the entry between the entries with the given client ids
+    pub fn reorder(
+        entry: &PlaylistEntry,
+        preceding_entry_id: Option<String>,
+        following_entry_id: Option<String>,
+    ) -> PlaylistEntryUpdate {
+        PlaylistEntryUpdate {
+            id: entry.id.clone(),
+            client_id: entry.client_id.clone(),
+            creation_timestamp: entry.creation_timestamp.clone(),
+            last_modified_timestamp: entry.last_modified_timestamp.clone(),
+            deleted: entry.deleted,
+            playlist_id: entry.playlist_id.clone(),
+            track_id: entry.track_id.clone(),
+            absolute_position: entry.absolute_position.clone(),
+            source: entry.source.clone(),
+            preceding_entry_id,
+            following_entry_id,
+        }
+    }
+}
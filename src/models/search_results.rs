@@ -6,8 +6,10 @@ use serde::Deserialize;
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResultResponse {
+    #[allow(dead_code)]
     pub kind: String,
     pub cluster_detail: Vec<SearchResultCluster>,
+    #[allow(dead_code)]
     pub suggested_query: Option<String>,
 }
 
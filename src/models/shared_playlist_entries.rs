@@ -6,6 +6,7 @@ use crate::models::playlist_entries::GetPlaylistEntriesRequest;
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SharedPlaylistEntriesResponse {
+    #[allow(dead_code)]
     pub kind: String,
     pub entries: Vec<SharedPlaylist>,
 }
@@ -13,7 +14,9 @@ pub struct SharedPlaylistEntriesResponse {
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SharedPlaylist {
+    #[allow(dead_code)]
     pub share_token: String,
+    #[allow(dead_code)]
     pub response_code: String,
     #[serde(default)]
     pub playlist_entry: Vec<SharedPlaylistEntry>,
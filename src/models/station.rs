@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::image_ref::ImageRef;
+use crate::{Album, Artist, Track};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Station {
+    #[serde(default)]
+    pub id: String,
+    pub kind: String,
+    pub client_id: Option<String>,
+    pub name: String,
+    pub deleted: Option<bool>,
+    pub last_modified_timestamp: Option<String>,
+    pub recent_timestamp: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub image_urls: Vec<ImageRef>,
+    pub in_library: Option<bool>,
+    pub seed: StationSeed,
+    #[serde(default)]
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StationSeed {
+    pub seed_type: StationSeedType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_locker_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playlist_share_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curated_station_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum StationSeedType {
+    #[serde(rename = "1")]
+    LibraryTrack,
+    #[serde(rename = "2")]
+    StoreTrack,
+    #[serde(rename = "3")]
+    Artist,
+    #[serde(rename = "4")]
+    Album,
+    #[serde(rename = "5")]
+    Genre,
+    #[serde(rename = "8")]
+    Playlist,
+    #[serde(rename = "9")]
+    CuratedStation,
+    #[serde(other, skip_serializing)]
+    Unknown,
+}
+
+impl StationSeed {
+    fn new(seed_type: StationSeedType) -> StationSeed {
+        StationSeed {
+            seed_type,
+            track_locker_id: None,
+            track_id: None,
+            artist_id: None,
+            album_id: None,
+            genre_id: None,
+            playlist_share_token: None,
+            curated_station_id: None,
+        }
+    }
+
+    pub fn genre<S: Into<String>>(genre_id: S) -> StationSeed {
+        StationSeed {
+            genre_id: Some(genre_id.into()),
+            ..StationSeed::new(StationSeedType::Genre)
+        }
+    }
+
+    pub fn playlist<S: Into<String>>(share_token: S) -> StationSeed {
+        StationSeed {
+            playlist_share_token: Some(share_token.into()),
+            ..StationSeed::new(StationSeedType::Playlist)
+        }
+    }
+
+    pub fn curated_station<S: Into<String>>(station_id: S) -> StationSeed {
+        StationSeed {
+            curated_station_id: Some(station_id.into()),
+            ..StationSeed::new(StationSeedType::CuratedStation)
+        }
+    }
+}
+
+impl From<&Track> for StationSeed {
+    fn from(track: &Track) -> Self {
+        if track.id.starts_with('T') {
+            StationSeed {
+                track_id: Some(track.id.clone()),
+                ..StationSeed::new(StationSeedType::StoreTrack)
+            }
+        } else if let Some(store_id) = track.store_id.as_ref() {
+            StationSeed {
+                track_id: Some(store_id.clone()),
+                ..StationSeed::new(StationSeedType::StoreTrack)
+            }
+        } else {
+            StationSeed {
+                track_locker_id: Some(track.id.clone()),
+                ..StationSeed::new(StationSeedType::LibraryTrack)
+            }
+        }
+    }
+}
+
+impl From<&Album> for StationSeed {
+    fn from(album: &Album) -> Self {
+        StationSeed {
+            album_id: Some(album.id.clone()),
+            ..StationSeed::new(StationSeedType::Album)
+        }
+    }
+}
+
+impl From<&Artist> for StationSeed {
+    fn from(artist: &Artist) -> Self {
+        StationSeed {
+            artist_id: Some(artist.id.clone()),
+            ..StationSeed::new(StationSeedType::Artist)
+        }
+    }
+}
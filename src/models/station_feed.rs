@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::GMusicResponse;
+use crate::Track;
+
+pub type StationFeedResponse = GMusicResponse<StationFeed>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationFeedRequest {
+    pub content_filter: u32,
+    pub stations: Vec<StationFeedFilter>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationFeedFilter {
+    pub radio_id: String,
+    pub num_entries: u32,
+    pub recently_played: Vec<RecentlyPlayedTrack>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentlyPlayedTrack {
+    pub id: String,
+    /// 1 for store tracks, 0 for library tracks
+    #[serde(rename = "type")]
+    pub track_type: u8,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct StationFeed {
+    #[serde(default)]
+    pub stations: Vec<StationFeedEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StationFeedEntry {
+    #[serde(default)]
+    pub tracks: Vec<Track>,
+}
+
+impl RecentlyPlayedTrack {
+    pub fn new(id: &str) -> RecentlyPlayedTrack {
+        RecentlyPlayedTrack {
+            id: id.to_string(),
+            track_type: if id.starts_with('T') { 1 } else { 0 },
+        }
+    }
+}
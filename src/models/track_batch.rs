@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+use crate::models::all_tracks::{Track, TrackRating};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackMutation {
+    Create(TrackCreate),
+    Update(Track),
+    Delete(String),
+}
+
+/// Adds a store track to the library
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackCreate {
+    pub store_id: String,
+    pub nid: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub album_artist: Option<String>,
+    pub album_id: Option<String>,
+    pub track_number: u64,
+    pub total_track_count: Option<u64>,
+    pub disc_number: Option<u64>,
+    pub total_disc_count: u64,
+    pub duration_millis: String,
+    pub year: Option<u64>,
+    pub genre: String,
+    pub composer: String,
+    pub beats_per_minute: i64,
+    pub play_count: u64,
+    pub rating: TrackRating,
+    pub deleted: bool,
+    pub creation_timestamp: String,
+    pub last_modified_timestamp: String,
+    /// 8 marks a store track added to the library
+    pub track_type: u8,
+}
+
+impl TrackCreate {
+    pub fn new(store_id: String, track: &Track) -> TrackCreate {
+        TrackCreate {
+            nid: store_id.clone(),
+            store_id,
+            title: track.title.clone(),
+            artist: track.artist.clone(),
+            album: track.album.clone(),
+            album_artist: track.album_artist.clone(),
+            album_id: track.album_id.clone(),
+            track_number: track.track_number,
+            total_track_count: track.total_track_count,
+            disc_number: track.disc_number,
+            total_disc_count: track.total_disc_count.unwrap_or_default(),
+            duration_millis: track.duration_millis.clone(),
+            year: track.year,
+            genre: track.genre.clone().unwrap_or_default(),
+            composer: String::new(),
+            beats_per_minute: track.beats_per_minute.map(|bpm| bpm as i64).unwrap_or(-1),
+            play_count: 0,
+            rating: TrackRating::None,
+            deleted: false,
+            creation_timestamp: String::from("-1"),
+            last_modified_timestamp: String::from("0"),
+            track_type: 8,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackStatsRequest {
+    pub track_stats: Vec<TrackStat>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackStat {
+    pub id: String,
+    pub incremental_plays: u64,
+    pub last_play_time_millis: String,
+    /// 1 for library tracks, 2 for store tracks
+    #[serde(rename = "type")]
+    pub track_type: u8,
+    pub track_events: Vec<TrackEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackEvent {
+    pub context_type: u32,
+    pub event_timestamp_micros: String,
+    pub event_type: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrackStatsResponse {
+    #[serde(default)]
+    pub responses: Vec<TrackStatsResult>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrackStatsResult {
+    pub id: String,
+    pub response_code: String,
+}
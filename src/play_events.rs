@@ -0,0 +1,182 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GmusicError;
+use crate::GoogleMusicApi;
+
+/// Context type used by the official clients for regular playback
+pub static DEFAULT_PLAY_CONTEXT: u32 = 1;
+
+/**
+ * A single play of a library or store track
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayEvent {
+    pub track_id: String,
+    /// Microseconds since the unix epoch
+    pub played_at: u64,
+    pub context_type: u32,
+}
+
+impl PlayEvent {
+    pub fn new<S: Into<String>>(
+        track_id: S,
+        played_at: SystemTime,
+    ) -> Result<PlayEvent, GmusicError> {
+        Ok(PlayEvent {
+            track_id: track_id.into(),
+            played_at: played_at.duration_since(UNIX_EPOCH)?.as_micros() as u64,
+            context_type: DEFAULT_PLAY_CONTEXT,
+        })
+    }
+
+    pub fn with_context(mut self, context_type: u32) -> PlayEvent {
+        self.context_type = context_type;
+        self
+    }
+
+    pub fn played_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_micros(self.played_at)
+    }
+}
+
+/**
+ * Collects play events to report them in batches
+ *
+ * The queue can be serialized to keep events recorded while offline.
+ *
+ * # Example
+ * ```rust,no_run
+ * use std::time::SystemTime;
+ * use gmusic::{GoogleMusicApi, PlayEvent, PlayEventQueue};
+ *
+ * #[tokio::main]
+ * async fn main() {
+ *   let api = GoogleMusicApi::new(String::new(), String::new(), None).unwrap();
+ *   api.load_token().await.unwrap();
+ *
+ *   let mut queue = PlayEventQueue::new();
+ *   queue.push(PlayEvent::new("Tj6fhurtstzgdpvfm4xv6i5cei4", SystemTime::now()).unwrap());
+ *   queue.flush(&api).await.unwrap();
+ * }
+ * ```
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayEventQueue {
+    events: Vec<PlayEvent>,
+}
+
+impl PlayEventQueue {
+    /// Maximum amount of events reported in a single request
+    pub const BATCH_SIZE: usize = 100;
+
+    pub fn new() -> PlayEventQueue {
+        PlayEventQueue::default()
+    }
+
+    pub fn push(&mut self, event: PlayEvent) {
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[PlayEvent] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /**
+     * Reports all queued events in batches
+     *
+     * Events are removed from the queue once the server counted them.
+     * Events of tracks the server rejected stay queued, as do all remaining events when a request fails.
+     * Returns the number of reported events.
+     */
+    pub async fn flush(&mut self, api: &GoogleMusicApi) -> Result<usize, GmusicError> {
+        let mut reported = 0;
+        let mut rejected = Vec::new();
+        let result = loop {
+            if self.events.is_empty() {
+                break Ok(());
+            }
+            let batch_size = self.events.len().min(PlayEventQueue::BATCH_SIZE);
+            match api.report_plays(&self.events[..batch_size]).await {
+                Ok(failed) => reported += self.complete_batch(batch_size, &failed, &mut rejected),
+                Err(err) => break Err(err),
+            }
+        };
+        rejected.append(&mut self.events);
+        self.events = rejected;
+
+        result.map(|_| reported)
+    }
+
+    /**
+     * Removes the first `batch_size` events, moving the ones of failed tracks to `rejected`
+     *
+     * Returns the number of accepted events.
+     */
+    fn complete_batch(
+        &mut self,
+        batch_size: usize,
+        failed: &[String],
+        rejected: &mut Vec<PlayEvent>,
+    ) -> usize {
+        let (failed_events, accepted): (Vec<_>, Vec<_>) = self
+            .events
+            .drain(..batch_size)
+            .partition(|event| failed.contains(&event.track_id));
+        rejected.extend(failed_events);
+
+        accepted.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(track_id: &str, played_at: u64) -> PlayEvent {
+        PlayEvent {
+            track_id: track_id.to_string(),
+            played_at,
+            context_type: DEFAULT_PLAY_CONTEXT,
+        }
+    }
+
+    #[test]
+    fn complete_batch_keeps_only_failed_events() {
+        let mut queue = PlayEventQueue::new();
+        queue.push(event("a", 1));
+        queue.push(event("b", 2));
+        queue.push(event("a", 3));
+        queue.push(event("c", 4));
+        let mut rejected = Vec::new();
+
+        let accepted = queue.complete_batch(3, &["a".to_string()], &mut rejected);
+
+        assert_eq!(accepted, 1);
+        assert_eq!(rejected, vec![event("a", 1), event("a", 3)]);
+        assert_eq!(queue.events(), &[event("c", 4)]);
+    }
+
+    #[test]
+    fn complete_batch_drains_accepted_batch() {
+        let mut queue = PlayEventQueue::new();
+        queue.push(event("a", 1));
+        queue.push(event("b", 2));
+        let mut rejected = Vec::new();
+
+        let accepted = queue.complete_batch(2, &[], &mut rejected);
+
+        assert_eq!(accepted, 2);
+        assert!(rejected.is_empty());
+        assert!(queue.is_empty());
+    }
+}
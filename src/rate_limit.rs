@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::time::delay_for;
+
+/**
+ * Groups of endpoints which can be limited independently
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointCategory {
+    /// Track, playlist and playlist entry feeds and device infos
+    Library,
+    /// Store tracks, albums, artists and search
+    Catalog,
+    /// Changes to playlists, tracks and stations and play reports
+    Mutation,
+    /// Station listing and station tracks
+    Radio,
+    /// Stream urls
+    Stream,
+}
+
+impl EndpointCategory {
+    pub(crate) fn from_endpoint(endpoint: &str) -> EndpointCategory {
+        match endpoint {
+            "fetchtrack" | "fetchalbum" | "fetchartist" | "query" => EndpointCategory::Catalog,
+            "playlistbatch" | "plentriesbatch" | "trackbatch" | "trackstats"
+            | "radio/editstation" => EndpointCategory::Mutation,
+            "radio/station" | "radio/stationfeed" => EndpointCategory::Radio,
+            _ => EndpointCategory::Library,
+        }
+    }
+}
+
+/**
+ * Allows `requests` requests per `period` on average with bursts of up to `burst` requests
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    requests: u32,
+    period: Duration,
+    burst: u32,
+}
+
+impl RateLimit {
+    /**
+     * A zero `period` disables the limit
+     */
+    pub fn new(requests: u32, period: Duration) -> RateLimit {
+        RateLimit {
+            requests,
+            period,
+            burst: requests,
+        }
+    }
+
+    pub fn per_second(requests: u32) -> RateLimit {
+        RateLimit::new(requests, Duration::from_secs(1))
+    }
+
+    pub fn per_minute(requests: u32) -> RateLimit {
+        RateLimit::new(requests, Duration::from_secs(60))
+    }
+
+    /**
+     * Number of requests which can be sent at once after being idle, defaults to `requests`
+     */
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    capacity: f64,
+    /// Tokens added per second, infinite for a zero period
+    rate: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Bucket {
+        let capacity = f64::from(limit.burst.max(1));
+        Bucket {
+            tokens: capacity,
+            capacity,
+            rate: if limit.period == Duration::new(0, 0) {
+                f64::INFINITY
+            } else {
+                f64::from(limit.requests.max(1)) / limit.period.as_secs_f64()
+            },
+            updated_at: Instant::now(),
+        }
+    }
+
+    /**
+     * Takes a token and returns how long to wait until it is available
+     *
+     * The token is reserved immediately so concurrent callers queue up behind each other.
+     */
+    fn reserve(&mut self, now: Instant) -> Duration {
+        if self.rate.is_infinite() {
+            return Duration::new(0, 0);
+        }
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated_at = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::new(0, 0)
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/**
+ * Token bucket rate limiter, clones share the same buckets
+ *
+ * Endpoints of categories without their own limit share the default bucket.
+ * Sessions created with `new_session` share the limiter as well.
+ *
+ * # Example
+ * ```rust,no_run
+ * use gmusic::{EndpointCategory, GoogleMusicApi, RateLimit, RateLimiter};
+ *
+ * let limiter = RateLimiter::new()
+ *   .with_default_limit(RateLimit::per_second(10))
+ *   .with_limit(EndpointCategory::Catalog, RateLimit::per_second(2).with_burst(5));
+ * let api = GoogleMusicApi::builder(String::new(), String::new())
+ *   .with_rate_limiter(limiter)
+ *   .build()
+ *   .unwrap();
+ * ```
+ */
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    default: Option<Arc<Mutex<Bucket>>>,
+    categories: HashMap<EndpointCategory, Arc<Mutex<Bucket>>>,
+}
+
+impl RateLimiter {
+    /**
+     * Creates a limiter without any limits
+     */
+    pub fn new() -> RateLimiter {
+        RateLimiter::default()
+    }
+
+    pub fn with_default_limit(mut self, limit: RateLimit) -> Self {
+        self.default = Some(Arc::new(Mutex::new(Bucket::new(limit))));
+        self
+    }
+
+    pub fn with_limit(mut self, category: EndpointCategory, limit: RateLimit) -> Self {
+        self.categories
+            .insert(category, Arc::new(Mutex::new(Bucket::new(limit))));
+        self
+    }
+
+    /**
+     * Waits until a request of the given category may be sent
+     */
+    pub(crate) async fn acquire(&self, category: EndpointCategory) {
+        let bucket = match self.categories.get(&category).or(self.default.as_ref()) {
+            Some(bucket) => bucket,
+            None => return,
+        };
+        let delay = bucket
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .reserve(Instant::now());
+        if delay > Duration::new(0, 0) {
+            delay_for(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn allows_initial_burst() {
+        let mut bucket = Bucket::new(RateLimit::per_second(1).with_burst(3));
+        let now = bucket.updated_at;
+
+        assert_eq!(bucket.reserve(now), millis(0));
+        assert_eq!(bucket.reserve(now), millis(0));
+        assert_eq!(bucket.reserve(now), millis(0));
+        assert_eq!(bucket.reserve(now), millis(1000));
+    }
+
+    #[test]
+    fn waits_for_refill() {
+        let mut bucket = Bucket::new(RateLimit::per_second(2).with_burst(1));
+        let start = bucket.updated_at;
+
+        assert_eq!(bucket.reserve(start), millis(0));
+        assert_eq!(bucket.reserve(start + millis(100)), millis(400));
+        // the token reserved at 100ms is refilled at 500ms, the next one at 1000ms
+        assert_eq!(bucket.reserve(start + millis(500)), millis(500));
+        assert_eq!(bucket.reserve(start + millis(2000)), millis(0));
+    }
+
+    #[test]
+    fn concurrent_reservations_queue_up() {
+        let mut bucket = Bucket::new(RateLimit::per_second(4).with_burst(1));
+        let now = bucket.updated_at;
+
+        let delays: Vec<_> = (0..4).map(|_| bucket.reserve(now)).collect();
+
+        assert_eq!(
+            delays,
+            vec![millis(0), millis(250), millis(500), millis(750)]
+        );
+    }
+
+    #[test]
+    fn refill_is_limited_to_burst() {
+        let mut bucket = Bucket::new(RateLimit::per_second(10).with_burst(2));
+        let later = bucket.updated_at + Duration::from_secs(60);
+
+        assert_eq!(bucket.reserve(later), millis(0));
+        assert_eq!(bucket.reserve(later), millis(0));
+        assert_eq!(bucket.reserve(later), millis(100));
+    }
+
+    #[test]
+    fn zero_period_is_unlimited() {
+        let mut bucket = Bucket::new(RateLimit::new(1, Duration::new(0, 0)).with_burst(1));
+        let now = bucket.updated_at;
+
+        for _ in 0..10 {
+            assert_eq!(bucket.reserve(now), millis(0));
+        }
+        assert_eq!(bucket.reserve(now + millis(10)), millis(0));
+    }
+}
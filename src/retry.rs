@@ -0,0 +1,322 @@
+use std::time::{Duration, SystemTime};
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::error::GmusicError;
+
+/**
+ * Configures how often and how long to wait before failed requests are sent again
+ *
+ * Requests are retried on connection errors, timeouts, HTTP 429 and 5xx responses.
+ * Mutations and play reports are not idempotent, they are only retried when the connection
+ * could not be established or on HTTP 429, when the server did not apply them.
+ * The delay grows exponentially with each attempt and is randomized, a `Retry-After`
+ * header sent by the server takes precedence.
+ *
+ * # Example
+ * ```rust,no_run
+ * use std::time::Duration;
+ * use gmusic::{GoogleMusicApi, RetryPolicy};
+ *
+ * let policy = RetryPolicy::new()
+ *   .with_max_attempts(5)
+ *   .with_base_delay(Duration::from_secs(1));
+ * let api = GoogleMusicApi::builder(String::new(), String::new())
+ *   .with_retry_policy(policy)
+ *   .build()
+ *   .unwrap();
+ * ```
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    /**
+     * Tries each request up to 3 times, starting with a delay of 500ms
+     */
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /**
+     * Sends each request only once
+     */
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::default().with_max_attempts(1)
+    }
+
+    /**
+     * Number of times a request is sent including the first attempt
+     */
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /**
+     * Upper bound of the delay before the first retry, doubled with each further retry
+     */
+    pub fn with_base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /**
+     * Upper bound of the delay between two attempts
+     *
+     * Requests are not retried when the server asks to wait longer than this.
+     */
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /**
+     * Returns how long to wait before sending the request again or `None` when it should not be retried
+     *
+     * `attempt` is the number of the failed attempt, starting at 1.
+     */
+    pub(crate) fn retry_delay(
+        &self,
+        attempt: u32,
+        err: &GmusicError,
+        retry_after: Option<Duration>,
+        idempotent: bool,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !RetryPolicy::is_transient(err, idempotent) {
+            return None;
+        }
+        match retry_after {
+            Some(retry_after) if retry_after > self.max_delay => None,
+            Some(retry_after) => Some(retry_after),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt - 1);
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        // full jitter spreads retries of concurrent requests
+        let millis = delay.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0, millis + 1))
+    }
+
+    /**
+     * Requests which are not idempotent are only retried when the server did not receive them
+     */
+    fn is_transient(err: &GmusicError, idempotent: bool) -> bool {
+        match err {
+            GmusicError::RateLimited { .. } => true,
+            GmusicError::Http(err) if err.is_connect() => true,
+            GmusicError::Status(status) => idempotent && status.is_server_error(),
+            GmusicError::Http(_) => idempotent,
+            _ => false,
+        }
+    }
+}
+
+/**
+ * Parses the `Retry-After` header, which is either a number of seconds or a http date
+ */
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or_else(|_| Duration::new(0, 0)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use reqwest::StatusCode;
+
+    async fn connect_error() -> GmusicError {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let err = client
+            .get(&format!("http://127.0.0.1:{}", port))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_connect());
+        GmusicError::from(err)
+    }
+
+    async fn timeout_error() -> GmusicError {
+        // the connection is accepted by the backlog but never answered
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let err = client
+            .get(&format!("http://127.0.0.1:{}", port))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout());
+        GmusicError::from(err)
+    }
+
+    fn retry_after(value: &'static str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static(value));
+        parse_retry_after(&headers)
+    }
+
+    #[test]
+    fn server_errors_are_only_retried_when_idempotent() {
+        let policy = RetryPolicy::new();
+        let err = GmusicError::Status(StatusCode::SERVICE_UNAVAILABLE);
+
+        assert!(policy.retry_delay(1, &err, None, true).is_some());
+        assert_eq!(policy.retry_delay(1, &err, None, false), None);
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_only_retried_when_idempotent() {
+        let policy = RetryPolicy::new();
+        let err = timeout_error().await;
+
+        assert!(policy.retry_delay(1, &err, None, true).is_some());
+        assert_eq!(policy.retry_delay(1, &err, None, false), None);
+    }
+
+    #[tokio::test]
+    async fn connect_errors_are_always_retried() {
+        let policy = RetryPolicy::new();
+        let err = connect_error().await;
+
+        assert!(policy.retry_delay(1, &err, None, true).is_some());
+        assert!(policy.retry_delay(1, &err, None, false).is_some());
+    }
+
+    #[test]
+    fn rate_limits_are_always_retried() {
+        let policy = RetryPolicy::new();
+        let err = GmusicError::RateLimited { retry_after: None };
+
+        assert!(policy.retry_delay(1, &err, None, true).is_some());
+        assert!(policy.retry_delay(1, &err, None, false).is_some());
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let policy = RetryPolicy::new();
+
+        for err in &[
+            GmusicError::Unauthorized,
+            GmusicError::Forbidden,
+            GmusicError::NotFound,
+            GmusicError::Status(StatusCode::BAD_REQUEST),
+        ] {
+            assert_eq!(policy.retry_delay(1, err, None, true), None);
+        }
+    }
+
+    #[test]
+    fn stops_after_max_attempts() {
+        let policy = RetryPolicy::new().with_max_attempts(3);
+        let err = GmusicError::Status(StatusCode::BAD_GATEWAY);
+
+        assert!(policy.retry_delay(2, &err, None, true).is_some());
+        assert_eq!(policy.retry_delay(3, &err, None, true), None);
+        assert_eq!(RetryPolicy::none().retry_delay(1, &err, None, true), None);
+    }
+
+    #[test]
+    fn retry_after_takes_precedence_up_to_max_delay() {
+        let policy = RetryPolicy::new().with_max_delay(Duration::from_secs(10));
+        let err = GmusicError::RateLimited { retry_after: None };
+
+        assert_eq!(
+            policy.retry_delay(1, &err, Some(Duration::from_secs(10)), false),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            policy.retry_delay(1, &err, Some(Duration::from_secs(11)), false),
+            None
+        );
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        let policy = RetryPolicy::new()
+            .with_max_attempts(10)
+            .with_base_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(500));
+        let err = GmusicError::Status(StatusCode::INTERNAL_SERVER_ERROR);
+
+        for attempt in 1..10 {
+            let bound =
+                Duration::from_millis(100 * 2u64.pow(attempt - 1)).min(Duration::from_millis(500));
+            let delay = policy.retry_delay(attempt, &err, None, true).unwrap();
+            assert!(
+                delay <= bound,
+                "attempt {}: {:?} > {:?}",
+                attempt,
+                delay,
+                bound
+            );
+        }
+    }
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        assert_eq!(retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after("0"), Some(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn parses_retry_after_http_date() {
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+
+        let delay = parse_retry_after(&headers).unwrap();
+
+        assert!(delay > Duration::from_secs(58) && delay <= Duration::from_secs(60));
+        assert_eq!(
+            retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::from_secs(0))
+        );
+    }
+
+    #[test]
+    fn ignores_invalid_retry_after() {
+        assert_eq!(retry_after("soon"), None);
+        assert_eq!(retry_after("-1"), None);
+        assert_eq!(parse_retry_after(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,265 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GmusicError;
+use crate::{GoogleMusicApi, Playlist, PlaylistEntry, Track};
+
+/**
+ * Remembers when the library was last synced to only fetch changes on the next sync
+ *
+ * The sync time is the latest modification timestamp returned by the server, so a local clock
+ * which is ahead of the server can't cause changes to be skipped.
+ * The state can be serialized to persist it between runs.
+ *
+ * # Example
+ * ```rust,no_run
+ * use gmusic::{GoogleMusicApi, LibrarySyncState};
+ *
+ * #[tokio::main]
+ * async fn main() {
+ *   let api = GoogleMusicApi::new(String::new(), String::new(), None).unwrap();
+ *   api.load_token().await.unwrap();
+ *
+ *   let mut state = LibrarySyncState::new();
+ *   let changes = state.sync(&api).await.unwrap();
+ *   println!("{} new tracks", changes.tracks.added.len());
+ * }
+ * ```
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibrarySyncState {
+    /// Latest server modification timestamp in microseconds since the unix epoch
+    last_sync: Option<u64>,
+}
+
+/**
+ * Items which changed since the last sync
+ */
+#[derive(Debug, Clone)]
+pub struct Changes<T> {
+    pub added: Vec<T>,
+    pub modified: Vec<T>,
+    pub deleted: Vec<T>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LibraryChanges {
+    pub tracks: Changes<Track>,
+    pub playlists: Changes<Playlist>,
+    pub playlist_entries: Changes<PlaylistEntry>,
+}
+
+impl LibrarySyncState {
+    pub fn new() -> LibrarySyncState {
+        LibrarySyncState::default()
+    }
+
+    /**
+     * Returns the latest modification time seen by the last successful sync
+     */
+    pub fn last_sync(&self) -> Option<SystemTime> {
+        self.last_sync
+            .map(|micros| UNIX_EPOCH + Duration::from_micros(micros))
+    }
+
+    /**
+     * Fetches all tracks, playlists and playlist entries which changed since the last sync
+     *
+     * The first sync returns the whole library as added items.
+     * The sync timestamp is only updated when all feeds were fetched successfully.
+     */
+    pub async fn sync(&mut self, api: &GoogleMusicApi) -> Result<LibraryChanges, GmusicError> {
+        let (tracks, playlists, playlist_entries) = match self.last_sync() {
+            Some(since) => (
+                api.get_all_tracks_since(since).await?,
+                api.get_all_playlists_since(since).await?,
+                api.get_playlist_entries_since(since).await?,
+            ),
+            None => (
+                api.get_all_tracks().await?,
+                api.get_all_playlists().await?,
+                api.get_playlist_entries().await?,
+            ),
+        };
+        let latest = latest_timestamp(&tracks)
+            .max(latest_timestamp(&playlists))
+            .max(latest_timestamp(&playlist_entries));
+        let changes = LibraryChanges {
+            tracks: self.classify(tracks),
+            playlists: self.classify(playlists),
+            playlist_entries: self.classify(playlist_entries),
+        };
+        self.last_sync = self.last_sync.max(latest);
+
+        Ok(changes)
+    }
+
+    fn classify<T: SyncItem>(&self, items: Vec<T>) -> Changes<T> {
+        let mut changes = Changes {
+            added: Vec::new(),
+            modified: Vec::new(),
+            deleted: Vec::new(),
+        };
+        for item in items {
+            if item.is_deleted() {
+                changes.deleted.push(item);
+            } else if self.is_new(&item) {
+                changes.added.push(item);
+            } else {
+                changes.modified.push(item);
+            }
+        }
+        changes
+    }
+
+    fn is_new<T: SyncItem>(&self, item: &T) -> bool {
+        match (self.last_sync, item.creation_timestamp()) {
+            (Some(last_sync), Some(created)) => created > last_sync,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+fn latest_timestamp<T: SyncItem>(items: &[T]) -> Option<u64> {
+    items.iter().filter_map(SyncItem::modified_timestamp).max()
+}
+
+trait SyncItem {
+    fn is_deleted(&self) -> bool;
+
+    /// Microseconds since the unix epoch
+    fn creation_timestamp(&self) -> Option<u64>;
+
+    /// Microseconds since the unix epoch
+    fn modified_timestamp(&self) -> Option<u64>;
+}
+
+impl SyncItem for Track {
+    fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn creation_timestamp(&self) -> Option<u64> {
+        self.creation_timestamp.as_ref()?.parse().ok()
+    }
+
+    fn modified_timestamp(&self) -> Option<u64> {
+        self.last_modified_timestamp.as_ref()?.parse().ok()
+    }
+}
+
+impl SyncItem for Playlist {
+    fn is_deleted(&self) -> bool {
+        self.deleted.unwrap_or_default()
+    }
+
+    fn creation_timestamp(&self) -> Option<u64> {
+        self.creation_timestamp.as_ref()?.parse().ok()
+    }
+
+    fn modified_timestamp(&self) -> Option<u64> {
+        self.last_modified_timestamp.as_ref()?.parse().ok()
+    }
+}
+
+impl SyncItem for PlaylistEntry {
+    fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn creation_timestamp(&self) -> Option<u64> {
+        self.creation_timestamp.parse().ok()
+    }
+
+    fn modified_timestamp(&self) -> Option<u64> {
+        self.last_modified_timestamp.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Item {
+        deleted: bool,
+        created: Option<u64>,
+        modified: Option<u64>,
+    }
+
+    impl SyncItem for Item {
+        fn is_deleted(&self) -> bool {
+            self.deleted
+        }
+
+        fn creation_timestamp(&self) -> Option<u64> {
+            self.created
+        }
+
+        fn modified_timestamp(&self) -> Option<u64> {
+            self.modified
+        }
+    }
+
+    fn item(deleted: bool, created: Option<u64>, modified: Option<u64>) -> Item {
+        Item {
+            deleted,
+            created,
+            modified,
+        }
+    }
+
+    #[test]
+    fn first_sync_adds_everything() {
+        let state = LibrarySyncState::new();
+
+        let changes = state.classify(vec![
+            item(false, Some(10), Some(10)),
+            item(false, None, None),
+        ]);
+
+        assert_eq!(changes.added.len(), 2);
+        assert!(changes.modified.is_empty());
+        assert!(changes.deleted.is_empty());
+    }
+
+    #[test]
+    fn classify_compares_against_last_sync() {
+        let state = LibrarySyncState {
+            last_sync: Some(100),
+        };
+
+        let changes = state.classify(vec![
+            item(false, Some(150), Some(150)),
+            item(false, Some(50), Some(150)),
+            item(false, Some(100), Some(150)),
+            item(false, None, Some(150)),
+            item(true, Some(150), Some(150)),
+        ]);
+
+        assert_eq!(changes.added, vec![item(false, Some(150), Some(150))]);
+        assert_eq!(
+            changes.modified,
+            vec![
+                item(false, Some(50), Some(150)),
+                item(false, Some(100), Some(150)),
+                item(false, None, Some(150)),
+            ]
+        );
+        assert_eq!(changes.deleted, vec![item(true, Some(150), Some(150))]);
+    }
+
+    #[test]
+    fn latest_timestamp_uses_server_timestamps() {
+        let items = vec![
+            item(false, Some(10), Some(30)),
+            item(false, Some(10), None),
+            item(true, Some(10), Some(20)),
+        ];
+
+        assert_eq!(latest_timestamp(&items), Some(30));
+        assert_eq!(latest_timestamp::<Item>(&[]), None);
+    }
+}
@@ -1,19 +1,48 @@
+use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use failure::{format_err, Error};
-use log::debug;
-use oauth2::basic::{BasicClient, BasicTokenResponse};
-use oauth2::reqwest::async_http_client;
-use oauth2::{AsyncRefreshTokenRequest, TokenResponse};
+use futures::future::{abortable, AbortHandle, BoxFuture};
+use log::{debug, warn};
+use oauth2::basic::{BasicClient, BasicErrorResponseType, BasicTokenResponse};
+use oauth2::{AsyncRefreshTokenRequest, RequestTokenError, TokenResponse};
+use tokio::time::{delay_for, delay_until};
 
-#[derive(Debug, Clone)]
+use crate::auth::send_oauth_request;
+use crate::error::GmusicError;
+use crate::token_store::StoredToken;
+
+/// How long the background refresh waits while no token is set
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+/// How long the background refresh waits after a failed refresh
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+/// Minimum time between two background refreshes
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(crate) type TokenListener = Arc<dyn Fn(StoredToken) -> BoxFuture<'static, ()> + Send + Sync>;
+
+#[derive(Clone)]
 pub(crate) struct AuthToken {
     token: Arc<Mutex<Option<BasicTokenResponse>>>,
     has_token: Arc<AtomicBool>,
     expired_at: Arc<Mutex<Instant>>,
+    /// Lifetime of the current access token, `None` when the server didn't send one
+    lifetime: Arc<Mutex<Option<Duration>>>,
+    listener: Arc<RwLock<Option<TokenListener>>>,
+    refresh_lock: Arc<Mutex<()>>,
+}
+
+impl fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("token", &self.token)
+            .field("has_token", &self.has_token)
+            .field("expired_at", &self.expired_at)
+            .field("lifetime", &self.lifetime)
+            .finish()
+    }
 }
 
 impl AuthToken {
@@ -22,85 +51,337 @@ impl AuthToken {
             token: Arc::new(Mutex::new(None)),
             has_token: Arc::new(AtomicBool::new(false)),
             expired_at: Arc::new(Mutex::new(Instant::now())),
+            lifetime: Arc::new(Mutex::new(None)),
+            listener: Arc::new(RwLock::new(None)),
+            refresh_lock: Arc::new(Mutex::new(())),
         }
     }
 
+    /**
+     * Registers a listener which is awaited whenever the token changes, replacing the previous one
+     */
+    pub(crate) fn set_listener(&self, listener: TokenListener) {
+        let mut current = self.listener.write().unwrap_or_else(|err| err.into_inner());
+        *current = Some(listener);
+    }
+
     pub(crate) async fn set_token(&self, new_token: BasicTokenResponse) {
+        let expires_in = new_token
+            .expires_in()
+            .unwrap_or_else(|| Duration::new(0, 0));
+        self.replace_token(new_token, Instant::now() + expires_in)
+            .await;
+        self.notify().await;
+    }
+
+    /**
+     * Restores a persisted token without notifying the listener
+     */
+    pub(crate) async fn restore_token(&self, stored: StoredToken) {
+        let expired_at = match stored.expires_at() {
+            Some(expires_at) => {
+                let remaining = expires_at
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_else(|_| Duration::new(0, 0));
+                Instant::now() + remaining
+            }
+            None => {
+                Instant::now()
+                    + stored
+                        .token
+                        .expires_in()
+                        .unwrap_or_else(|| Duration::new(0, 0))
+            }
+        };
+        self.replace_token(stored.token, expired_at).await;
+    }
+
+    async fn replace_token(&self, new_token: BasicTokenResponse, new_expired_at: Instant) {
         let mut token = self.token.lock().await;
         let mut expired_at = self.expired_at.lock().await;
-        *expired_at = Instant::now()
-            + new_token
-                .expires_in()
-                .unwrap_or_else(|| Duration::new(0, 0));
+        *expired_at = new_expired_at;
+        *self.lifetime.lock().await = new_token.expires_in();
         *token = Some(new_token);
         self.has_token.store(true, Ordering::Relaxed);
     }
 
-    pub(crate) async fn get_token(&self) -> Result<BasicTokenResponse, Error> {
+    /**
+     * Removes the token without notifying the listener
+     */
+    pub(crate) async fn clear(&self) {
+        let mut token = self.token.lock().await;
+        *token = None;
+        self.has_token.store(false, Ordering::Relaxed);
+    }
+
+    pub(crate) async fn get_token(&self) -> Result<BasicTokenResponse, GmusicError> {
         Ok(self
             .token
             .lock()
             .await
             .as_ref()
-            .ok_or_else(|| format_err!("Not logged in"))?
+            .ok_or(GmusicError::NotLoggedIn)?
             .clone())
     }
 
+    /**
+     * Returns the token together with its absolute expiry time
+     */
+    pub(crate) async fn get_stored_token(&self) -> Result<StoredToken, GmusicError> {
+        let token = self.get_token().await?;
+        let expired_at = *self.expired_at.lock().await;
+        let expires_at = SystemTime::now() + expired_at.saturating_duration_since(Instant::now());
+
+        Ok(StoredToken::new(token, Some(expires_at)))
+    }
+
     pub(crate) fn has_token(&self) -> bool {
         self.has_token.load(Ordering::Relaxed)
     }
 
-    pub(crate) async fn refresh(&self, client: &BasicClient) -> Result<(), Error> {
+    /**
+     * Refreshes the access token unless it was already replaced since the rejected request was sent
+     *
+     * `rejected` is the authorization header of the request the server rejected.
+     */
+    pub(crate) async fn refresh(
+        &self,
+        client: &BasicClient,
+        http_client: &reqwest::Client,
+        rejected: &str,
+    ) -> Result<(), GmusicError> {
+        let _guard = self.refresh_lock.lock().await;
+        if self.get_auth_header().await? != rejected {
+            debug!("access token was refreshed concurrently");
+            return Ok(());
+        }
+        self.refresh_token(client, http_client).await
+    }
+
+    /**
+     * Refreshes the access token when it expires within the given margin
+     *
+     * Only one refresh is in flight at a time, concurrent callers wait for it to finish.
+     */
+    pub(crate) async fn refresh_if_expiring(
+        &self,
+        client: &BasicClient,
+        http_client: &reqwest::Client,
+        margin: Duration,
+    ) -> Result<(), GmusicError> {
+        if !self.expires_within(margin).await {
+            return Ok(());
+        }
+        let _guard = self.refresh_lock.lock().await;
+        if !self.expires_within(margin).await {
+            debug!("access token was refreshed concurrently");
+            return Ok(());
+        }
+        self.refresh_token(client, http_client).await
+    }
+
+    async fn refresh_token(
+        &self,
+        client: &BasicClient,
+        http_client: &reqwest::Client,
+    ) -> Result<(), GmusicError> {
         debug!("refreshing access token");
-        let token = {
-            let token = self.token.lock().await;
-            let refresh_token = token
-                .as_ref()
-                .ok_or_else(|| format_err!("Not logged in"))?
-                .refresh_token()
-                .ok_or_else(|| format_err!("No refresh token"))?;
-
-            client
-                .exchange_refresh_token(refresh_token)
-                .request_async(async_http_client)
-                .await
-        }?;
+        // the token is not locked during the request so concurrent requests keep using the current one
+        let refresh_token = self
+            .token
+            .lock()
+            .await
+            .as_ref()
+            .ok_or(GmusicError::NotLoggedIn)?
+            .refresh_token()
+            .ok_or(GmusicError::NotLoggedIn)?
+            .clone();
+        let token = client
+            .exchange_refresh_token(&refresh_token)
+            .request_async(|request| send_oauth_request(http_client, request))
+            .await
+            .map_err(|err| match err {
+                // the refresh token was revoked or expired
+                RequestTokenError::ServerResponse(ref response)
+                    if *response.error() == BasicErrorResponseType::InvalidGrant =>
+                {
+                    GmusicError::NotLoggedIn
+                }
+                err => GmusicError::from(err),
+            })?;
 
         self.set_access_token(token).await;
 
         Ok(())
     }
 
+    /**
+     * Spawns a task on the tokio runtime which refreshes the access token the given margin before it expires
+     *
+     * The margin is limited to half of the token lifetime and refreshes are at least a minute apart.
+     * Tokens without lifetime are left to the refresh before each request.
+     * The task runs until the returned handle is stopped or dropped.
+     */
+    pub(crate) fn spawn_background_refresh(
+        &self,
+        client: BasicClient,
+        http_client: reqwest::Client,
+        margin: Duration,
+    ) -> BackgroundRefresh {
+        let token = self.clone();
+        let (task, handle) = abortable(async move {
+            loop {
+                if !token.has_token() {
+                    delay_for(IDLE_INTERVAL).await;
+                    continue;
+                }
+                let lifetime = match *token.lifetime.lock().await {
+                    Some(lifetime) if lifetime > Duration::new(0, 0) => lifetime,
+                    _ => {
+                        debug!("access token has no lifetime, skipping background refresh");
+                        delay_for(IDLE_INTERVAL).await;
+                        continue;
+                    }
+                };
+                let margin = margin.min(lifetime / 2);
+                let refresh_at = token.expired_at.lock().await.checked_sub(margin);
+                if let Some(refresh_at) = refresh_at {
+                    delay_until(refresh_at.into()).await;
+                }
+                match token
+                    .refresh_if_expiring(&client, &http_client, margin)
+                    .await
+                {
+                    Ok(()) => delay_for(MIN_REFRESH_INTERVAL).await,
+                    Err(err) => {
+                        warn!("background token refresh failed: {}", err);
+                        delay_for(RETRY_INTERVAL).await;
+                    }
+                }
+            }
+        });
+        tokio::spawn(task);
+
+        BackgroundRefresh { handle }
+    }
+
     async fn set_access_token(&self, new_token: BasicTokenResponse) {
-        let mut token = self.token.lock().await;
-        if let Some(token) = token.as_mut() {
-            token.set_access_token(new_token.access_token().clone());
-            let mut expired_at = self.expired_at.lock().await;
-            *expired_at = Instant::now()
-                + new_token
-                    .expires_in()
-                    .unwrap_or_else(|| Duration::new(0, 0));
-            self.has_token.store(true, Ordering::Relaxed);
+        {
+            let mut token = self.token.lock().await;
+            if let Some(token) = token.as_mut() {
+                token.set_access_token(new_token.access_token().clone());
+                // google may rotate the refresh token, the old one stops working then
+                if let Some(refresh_token) = new_token.refresh_token() {
+                    token.set_refresh_token(Some(refresh_token.clone()));
+                }
+                let mut expired_at = self.expired_at.lock().await;
+                *expired_at = Instant::now()
+                    + new_token
+                        .expires_in()
+                        .unwrap_or_else(|| Duration::new(0, 0));
+                *self.lifetime.lock().await = new_token.expires_in();
+                self.has_token.store(true, Ordering::Relaxed);
+            } else {
+                return;
+            }
+        }
+        self.notify().await;
+    }
+
+    async fn notify(&self) {
+        let listener = self
+            .listener
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone();
+        if let Some(listener) = listener {
+            match self.get_stored_token().await {
+                Ok(token) => listener(token).await,
+                Err(err) => debug!("unable to notify token listener: {}", err),
+            }
         }
     }
 
-    pub(crate) async fn get_auth_header(&self) -> Result<String, Error> {
+    pub(crate) async fn get_auth_header(&self) -> Result<String, GmusicError> {
         let token = self.token.lock().await;
         let token = token
             .as_ref()
-            .ok_or_else(|| format_err!("Not logged in"))?
+            .ok_or(GmusicError::NotLoggedIn)?
             .access_token()
             .secret();
         Ok(format!("Bearer {}", token))
     }
 
-    pub(crate) async fn requires_new_token(&self) -> bool {
+    async fn expires_within(&self, margin: Duration) -> bool {
         let has_token = self.has_token.load(Ordering::Relaxed);
         if !has_token {
             true
         } else {
             let expired = self.expired_at.lock().await;
-            Instant::now().ge(&expired)
+            Instant::now() + margin >= *expired
         }
     }
 }
+
+/**
+ * Handle of the background token refresh, stops the refresh when dropped
+ *
+ * Returned by [refresh_in_background](struct.GoogleMusicApi.html#method.refresh_in_background).
+ */
+#[derive(Debug)]
+pub struct BackgroundRefresh {
+    handle: AbortHandle,
+}
+
+impl BackgroundRefresh {
+    pub fn stop(self) {}
+}
+
+impl Drop for BackgroundRefresh {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oauth2::basic::BasicTokenType;
+    use oauth2::{AccessToken, EmptyExtraTokenFields, RefreshToken, StandardTokenResponse};
+
+    fn token(access_token: &str, refresh_token: Option<&str>) -> BasicTokenResponse {
+        let mut token = StandardTokenResponse::new(
+            AccessToken::new(access_token.into()),
+            BasicTokenType::Bearer,
+            EmptyExtraTokenFields {},
+        );
+        token.set_refresh_token(refresh_token.map(|token| RefreshToken::new(token.into())));
+        token
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_refresh_token_when_not_rotated() {
+        let auth_token = AuthToken::new();
+        auth_token.set_token(token("old", Some("refresh"))).await;
+
+        auth_token.set_access_token(token("new", None)).await;
+
+        let stored = auth_token.get_token().await.unwrap();
+        assert_eq!(stored.access_token().secret(), "new");
+        assert_eq!(stored.refresh_token().unwrap().secret(), "refresh");
+    }
+
+    #[tokio::test]
+    async fn refresh_stores_rotated_refresh_token() {
+        let auth_token = AuthToken::new();
+        auth_token.set_token(token("old", Some("refresh"))).await;
+
+        auth_token
+            .set_access_token(token("new", Some("rotated")))
+            .await;
+
+        let stored = auth_token.get_token().await.unwrap();
+        assert_eq!(stored.access_token().secret(), "new");
+        assert_eq!(stored.refresh_token().unwrap().secret(), "rotated");
+    }
+}
@@ -0,0 +1,458 @@
+use std::env;
+use std::fmt::{self, Debug};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use argon2::Argon2;
+use async_trait::async_trait;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use oauth2::basic::BasicTokenResponse;
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, read, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::task::spawn_blocking;
+
+use crate::error::GmusicError;
+
+static DEFAULT_TOKEN_FILE: &str = ".google-auth.json";
+static XDG_TOKEN_FILE: &str = "google-auth.json";
+
+static ENCRYPTED_MAGIC: &[u8] = b"GMTK";
+const ENCRYPTED_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = 4 + 1 + SALT_LEN + NONCE_LEN;
+
+/**
+ * The auth and refresh token together with the absolute time the auth token expires at
+ *
+ * Deserializes plain token responses as well, those don't carry an expiry time.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredToken {
+    #[serde(flatten)]
+    pub token: BasicTokenResponse,
+    /// Seconds since the unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+}
+
+impl StoredToken {
+    pub fn new(token: BasicTokenResponse, expires_at: Option<SystemTime>) -> StoredToken {
+        let expires_at = expires_at
+            .and_then(|expires_at| expires_at.duration_since(UNIX_EPOCH).ok())
+            .map(|expires_at| expires_at.as_secs());
+        StoredToken { token, expires_at }
+    }
+
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+            .map(|expires_at| UNIX_EPOCH + Duration::from_secs(expires_at))
+    }
+}
+
+/**
+ * Persists the auth and refresh token for login without user input
+ *
+ * Used by [store_token](../struct.GoogleMusicApi.html#method.store_token) and
+ * [load_token](../struct.GoogleMusicApi.html#method.load_token).
+ */
+#[async_trait]
+pub trait TokenStore: Debug + Send + Sync {
+    async fn store(&self, token: &StoredToken) -> Result<(), GmusicError>;
+
+    /**
+     * Returns `None` when no token has been stored yet
+     */
+    async fn load(&self) -> Result<Option<StoredToken>, GmusicError>;
+
+    /**
+     * Removes the stored token, does nothing when no token is stored
+     */
+    async fn clear(&self) -> Result<(), GmusicError>;
+}
+
+/**
+ * Stores the token as json file, readable only by the current user
+ */
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> FileTokenStore {
+        FileTokenStore { path: path.into() }
+    }
+
+    /**
+     * Stores the token in `$XDG_CONFIG_HOME/<app_name>/google-auth.json`
+     *
+     * Falls back to `$HOME/.config` when `XDG_CONFIG_HOME` is not set.
+     */
+    pub fn xdg(app_name: &str) -> Result<FileTokenStore, GmusicError> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .ok_or_else(|| {
+                GmusicError::TokenStore("Neither XDG_CONFIG_HOME nor HOME are set".into())
+            })?;
+
+        Ok(FileTokenStore::new(
+            config_dir.join(app_name).join(XDG_TOKEN_FILE),
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn read(&self) -> Result<Option<Vec<u8>>, GmusicError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(read(&self.path).await?))
+    }
+
+    async fn write(&self, content: &[u8]) -> Result<(), GmusicError> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).await?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // the mode is only applied to new files
+            if self.path.exists() {
+                fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600)).await?;
+            }
+        }
+        let mut file = OpenOptions::from(options).open(&self.path).await?;
+        file.write_all(content).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+impl Default for FileTokenStore {
+    /**
+     * Stores the token in `.google-auth.json` in the current working directory
+     */
+    fn default() -> Self {
+        FileTokenStore::new(DEFAULT_TOKEN_FILE)
+    }
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn store(&self, token: &StoredToken) -> Result<(), GmusicError> {
+        let token = serde_json::to_string(token)?;
+        self.write(token.as_bytes()).await
+    }
+
+    async fn load(&self) -> Result<Option<StoredToken>, GmusicError> {
+        match self.read().await? {
+            Some(token) => Ok(Some(serde_json::from_slice(&token)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn clear(&self) -> Result<(), GmusicError> {
+        if self.path.exists() {
+            fs::remove_file(&self.path).await?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Keeps the token in memory, clones share the same token
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTokenStore {
+    token: Arc<Mutex<Option<StoredToken>>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> MemoryTokenStore {
+        MemoryTokenStore::default()
+    }
+}
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn store(&self, token: &StoredToken) -> Result<(), GmusicError> {
+        let mut stored = self
+            .token
+            .lock()
+            .map_err(|_| GmusicError::TokenStore("Token store lock poisoned".into()))?;
+        *stored = Some(token.clone());
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<Option<StoredToken>, GmusicError> {
+        let stored = self
+            .token
+            .lock()
+            .map_err(|_| GmusicError::TokenStore("Token store lock poisoned".into()))?;
+        Ok(stored.clone())
+    }
+
+    async fn clear(&self) -> Result<(), GmusicError> {
+        let mut stored = self
+            .token
+            .lock()
+            .map_err(|_| GmusicError::TokenStore("Token store lock poisoned".into()))?;
+        *stored = None;
+        Ok(())
+    }
+}
+
+/**
+ * Where the secret used to encrypt the token is taken from
+ */
+#[derive(Clone)]
+pub enum TokenKey {
+    Passphrase(String),
+    /// The whole content of the file is used as secret
+    KeyFile(PathBuf),
+}
+
+impl Debug for TokenKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKey::Passphrase(_) => f.write_str("Passphrase([redacted])"),
+            TokenKey::KeyFile(path) => f.debug_tuple("KeyFile").field(path).finish(),
+        }
+    }
+}
+
+impl TokenKey {
+    async fn secret(&self) -> Result<Vec<u8>, GmusicError> {
+        let secret = match self {
+            TokenKey::Passphrase(passphrase) => passphrase.as_bytes().to_vec(),
+            TokenKey::KeyFile(path) => read(path).await?,
+        };
+        if secret.is_empty() {
+            return Err(GmusicError::TokenStore(
+                "Token key must not be empty".into(),
+            ));
+        }
+        Ok(secret)
+    }
+
+    /**
+     * Derives the encryption key, the key derivation is expensive and runs on the blocking thread pool
+     */
+    async fn derive(&self, salt: &[u8]) -> Result<Key, GmusicError> {
+        let secret = self.secret().await?;
+        let salt = salt.to_vec();
+        spawn_blocking(move || {
+            let mut key = Key::default();
+            Argon2::default()
+                .hash_password_into(&secret, &salt, &mut key)
+                .map_err(|err| {
+                    GmusicError::TokenStore(format!("Unable to derive token key: {}", err))
+                })?;
+            Ok(key)
+        })
+        .await
+        .map_err(|err| GmusicError::TokenStore(format!("Unable to derive token key: {}", err)))?
+    }
+}
+
+/**
+ * Stores the token encrypted with a key derived from a passphrase or key file
+ *
+ * The key is derived with Argon2 using a random salt, the token is encrypted with ChaCha20-Poly1305.
+ * The file is only readable by the current user.
+ *
+ * # Example
+ * ```rust,no_run
+ * use gmusic::GoogleMusicApi;
+ * use gmusic::token_store::{EncryptedFileTokenStore, TokenKey};
+ *
+ * let key = TokenKey::KeyFile("/etc/my-player/token.key".into());
+ * let api = GoogleMusicApi::new(String::new(), String::new(), None)
+ *   .unwrap()
+ *   .with_token_store(EncryptedFileTokenStore::new(".google-auth.enc", key));
+ * ```
+ */
+#[derive(Debug, Clone)]
+pub struct EncryptedFileTokenStore {
+    file: FileTokenStore,
+    key: TokenKey,
+}
+
+impl EncryptedFileTokenStore {
+    pub fn new<P: Into<PathBuf>>(path: P, key: TokenKey) -> EncryptedFileTokenStore {
+        EncryptedFileTokenStore {
+            file: FileTokenStore::new(path),
+            key,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+}
+
+#[async_trait]
+impl TokenStore for EncryptedFileTokenStore {
+    async fn store(&self, token: &StoredToken) -> Result<(), GmusicError> {
+        let token = serde_json::to_vec(token)?;
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(ENCRYPTED_MAGIC);
+        header.push(ENCRYPTED_VERSION);
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        header.extend_from_slice(&salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        header.extend_from_slice(&nonce);
+
+        let key = self.key.derive(&salt).await?;
+        let payload = Payload {
+            msg: &token,
+            aad: &header,
+        };
+        let ciphertext = ChaCha20Poly1305::new(&key)
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| GmusicError::TokenStore("Unable to encrypt token".into()))?;
+
+        let mut content = header;
+        content.extend_from_slice(&ciphertext);
+        self.file.write(&content).await
+    }
+
+    async fn load(&self) -> Result<Option<StoredToken>, GmusicError> {
+        let content = match self.file.read().await? {
+            Some(content) => content,
+            None => return Ok(None),
+        };
+        if content.len() <= HEADER_LEN || !content.starts_with(ENCRYPTED_MAGIC) {
+            return Err(GmusicError::TokenStore(format!(
+                "{} is not an encrypted token file",
+                self.path().display()
+            )));
+        }
+        let (header, ciphertext) = content.split_at(HEADER_LEN);
+        if header[ENCRYPTED_MAGIC.len()] != ENCRYPTED_VERSION {
+            return Err(GmusicError::TokenStore(format!(
+                "Unsupported encrypted token file version {}",
+                header[ENCRYPTED_MAGIC.len()]
+            )));
+        }
+        let salt = &header[ENCRYPTED_MAGIC.len() + 1..HEADER_LEN - NONCE_LEN];
+        let nonce = &header[HEADER_LEN - NONCE_LEN..];
+
+        let key = self.key.derive(salt).await?;
+        let payload = Payload {
+            msg: ciphertext,
+            aad: header,
+        };
+        let token = ChaCha20Poly1305::new(&key)
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| {
+                GmusicError::TokenStore(
+                    "Unable to decrypt token, wrong key or modified file".into(),
+                )
+            })?;
+
+        Ok(Some(serde_json::from_slice(&token)?))
+    }
+    async fn clear(&self) -> Result<(), GmusicError> {
+        self.file.clear().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use oauth2::TokenResponse;
+
+    use super::*;
+
+    fn token() -> StoredToken {
+        let token = r#"{"access_token":"access","token_type":"bearer","expires_in":3600,"refresh_token":"refresh"}"#;
+        serde_json::from_str(token).unwrap()
+    }
+
+    fn store(key: &str) -> EncryptedFileTokenStore {
+        let path = env::temp_dir().join(format!("gmusic-{}.enc", uuid::Uuid::new_v4()));
+        EncryptedFileTokenStore::new(path, TokenKey::Passphrase(key.to_string()))
+    }
+
+    async fn modify<F: FnOnce(&mut Vec<u8>)>(store: &EncryptedFileTokenStore, modify: F) {
+        let mut content = read(store.path()).await.unwrap();
+        modify(&mut content);
+        store.file.write(&content).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn encrypted_store_roundtrip() {
+        let store = store("secret");
+        store.store(&token()).await.unwrap();
+
+        let loaded = store.load().await.unwrap().unwrap();
+
+        assert_eq!(loaded.token.refresh_token().unwrap().secret(), "refresh");
+        assert_eq!(loaded.token.access_token().secret(), "access");
+        store.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn encrypted_store_rejects_wrong_key() {
+        let store = store("secret");
+        store.store(&token()).await.unwrap();
+        let other =
+            EncryptedFileTokenStore::new(store.path(), TokenKey::Passphrase("other".to_string()));
+
+        let result = other.load().await;
+
+        assert!(matches!(result, Err(GmusicError::TokenStore(_))));
+        store.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn encrypted_store_rejects_modified_ciphertext() {
+        let store = store("secret");
+        store.store(&token()).await.unwrap();
+        modify(&store, |content| content[HEADER_LEN] ^= 1).await;
+
+        let result = store.load().await;
+
+        assert!(matches!(result, Err(GmusicError::TokenStore(_))));
+        store.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn encrypted_store_rejects_bad_magic() {
+        let store = store("secret");
+        store.store(&token()).await.unwrap();
+        modify(&store, |content| content[0] = b'X').await;
+
+        let result = store.load().await;
+
+        assert!(matches!(result, Err(GmusicError::TokenStore(_))));
+        store.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn encrypted_store_rejects_unknown_version() {
+        let store = store("secret");
+        store.store(&token()).await.unwrap();
+        modify(&store, |content| content[ENCRYPTED_MAGIC.len()] = 2).await;
+
+        let result = store.load().await;
+
+        assert!(matches!(result, Err(GmusicError::TokenStore(_))));
+        store.clear().await.unwrap();
+    }
+}